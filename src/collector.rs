@@ -1,5 +1,7 @@
 use oas3::Spec;
-use oas3::spec::{ObjectOrReference, ObjectSchema, Operation, RequestBody, Schema};
+use oas3::spec::{
+    ObjectOrReference, ObjectSchema, Operation, Parameter, PathItem, RequestBody, Schema, Server,
+};
 
 /// Op is the struct that represents an operation in the `OpenAPI` spec.
 #[derive(Clone)]
@@ -7,9 +9,25 @@ pub struct Op {
     pub path: String,
     pub method: String,
     pub operation: Operation,
+    /// Parameters from the path item merged with the operation's own, already
+    /// resolved from any `$ref`.
+    pub parameters: Vec<Parameter>,
     pub payload: Option<ObjectSchema>,
+    /// Why the request body schema could not be resolved, leaving `payload`
+    /// empty for an operation that declares one.
+    pub payload_error: Option<String>,
+    /// Media type of the request body picked from the spec, sent as the
+    /// `Content-Type` header. `None` when the operation carries no body.
+    pub content_type: Option<String>,
+    /// The operation's own `servers`, else its path item's. Empty when the
+    /// spec-level ones apply.
+    pub servers: Vec<Server>,
 }
 
+/// Media types drilled as JSON bodies. JSON Merge Patch is plain JSON on the
+/// wire, so PATCH endpoints declaring it are drilled the same way.
+const JSON_MEDIA_TYPES: [&str; 2] = ["application/json", "application/merge-patch+json"];
+
 pub fn collect_gets(spec: &Spec) -> Vec<Op> {
     let Some(paths) = &spec.paths else {
         return vec![];
@@ -26,12 +44,48 @@ pub fn collect_gets(spec: &Spec) -> Vec<Op> {
                 path: path.clone(),
                 method: "GET".to_owned(),
                 operation: op.clone(),
+                parameters: collect_parameters(item, op, spec),
                 payload: None,
+                payload_error: None,
+                content_type: None,
+                servers: collect_servers(item, op),
             })
         })
         .collect()
 }
 
+/// Collects DELETE operations. Unlike POST and PUT the body is optional, so
+/// every operation is kept and the payload is filled only when one is declared.
+pub fn collect_delete(spec: &Spec) -> Vec<Op> {
+    let Some(paths) = &spec.paths else {
+        return vec![];
+    };
+
+    let mut ops: Vec<Op> = paths
+        .iter()
+        .filter_map(|(path, item)| {
+            let op = item.delete.as_ref()?;
+            if op.deprecated.unwrap_or(false) {
+                return None;
+            }
+            Some(Op {
+                path: path.clone(),
+                method: "DELETE".to_owned(),
+                operation: op.clone(),
+                parameters: collect_parameters(item, op, spec),
+                payload: None,
+                payload_error: None,
+                content_type: None,
+                servers: collect_servers(item, op),
+            })
+        })
+        .collect();
+
+    populate_payload(&mut ops, spec);
+
+    ops
+}
+
 pub fn collect_post(spec: &Spec) -> Vec<Op> {
     collect_with_body(spec, "POST", |item| item.post.as_ref())
 }
@@ -40,13 +94,17 @@ pub fn collect_put(spec: &Spec) -> Vec<Op> {
     collect_with_body(spec, "PUT", |item| item.put.as_ref())
 }
 
-/// Collects operations that carry a JSON request body (POST, PUT). The `pick`
+pub fn collect_patch(spec: &Spec) -> Vec<Op> {
+    collect_with_body(spec, "PATCH", |item| item.patch.as_ref())
+}
+
+/// Collects operations that carry a JSON request body (POST, PUT, PATCH). The `pick`
 /// closure selects which operation slot of the path item to read, so the same
 /// filtering and payload resolution is shared across methods.
 fn collect_with_body(
     spec: &Spec,
     method: &str,
-    pick: impl Fn(&oas3::spec::PathItem) -> Option<&Operation>,
+    pick: impl Fn(&PathItem) -> Option<&Operation>,
 ) -> Vec<Op> {
     let Some(paths) = &spec.paths else {
         return vec![];
@@ -62,15 +120,17 @@ fn collect_with_body(
 
             let req_body = resolve_request_body(op.request_body.as_ref()?, spec)?;
             // FIXME: in long term this should be required? :)
-            if !req_body.content.contains_key("application/json") {
-                return None;
-            }
+            json_media_type(&req_body)?;
 
             Some(Op {
                 path: path.clone(),
                 method: method.to_owned(),
                 operation: op.clone(),
+                parameters: collect_parameters(item, op, spec),
                 payload: None,
+                payload_error: None,
+                content_type: None,
+                servers: collect_servers(item, op),
             })
         })
         .collect();
@@ -80,6 +140,44 @@ fn collect_with_body(
     ops
 }
 
+/// Merges the parameters declared on the path item with the operation's own.
+/// An operation parameter overrides a path-level one with the same name and
+/// location, as the spec mandates.
+fn collect_parameters(item: &PathItem, op: &Operation, spec: &Spec) -> Vec<Parameter> {
+    let resolve = |p: &ObjectOrReference<Parameter>| match p.resolve(spec) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            tracing::warn!("cannot resolve parameter: {e}");
+            None
+        }
+    };
+
+    let own: Vec<Parameter> = op.parameters.iter().filter_map(resolve).collect();
+
+    let mut params: Vec<Parameter> = item
+        .parameters
+        .iter()
+        .filter_map(resolve)
+        .filter(|p| {
+            !own.iter()
+                .any(|o| o.name == p.name && o.location == p.location)
+        })
+        .collect();
+    params.extend(own);
+
+    params
+}
+
+/// The servers overriding the spec-level ones for an operation, the closest
+/// declaration winning.
+fn collect_servers(item: &PathItem, op: &Operation) -> Vec<Server> {
+    if op.servers.is_empty() {
+        item.servers.clone()
+    } else {
+        op.servers.clone()
+    }
+}
+
 /// Resolves a `RequestBody`, following a `$ref` when needed.
 fn resolve_request_body(
     req_body: &ObjectOrReference<RequestBody>,
@@ -88,6 +186,13 @@ fn resolve_request_body(
     req_body.resolve(spec).ok()
 }
 
+/// Returns the first JSON media type declared by the request body, if any.
+fn json_media_type(req_body: &RequestBody) -> Option<&'static str> {
+    JSON_MEDIA_TYPES
+        .into_iter()
+        .find(|m| req_body.content.contains_key(*m))
+}
+
 fn populate_payload(ops: &mut [Op], spec: &Spec) {
     for o in ops {
         let Some(req) = o.operation.request_body.as_ref() else {
@@ -98,9 +203,10 @@ fn populate_payload(ops: &mut [Op], spec: &Spec) {
             continue;
         };
 
-        let Some(media_type) = req.content.get("application/json") else {
+        let Some(content_type) = json_media_type(&req) else {
             continue;
         };
+        let media_type = &req.content[content_type];
 
         let Some(schema) = &media_type.schema else {
             tracing::warn!("no schema for request {}", o.path);
@@ -108,23 +214,201 @@ fn populate_payload(ops: &mut [Op], spec: &Spec) {
         };
 
         match resolve_object_schema(schema, spec) {
-            Ok(obj) => o.payload = Some(obj),
-            Err(e) => tracing::warn!("cannot resolve payload schema for {}: {e}", o.path),
+            Ok(mut obj) => {
+                // A media type example overrides the one on the schema, so it
+                // takes its place as the object-level example.
+                if let Some(example) = media_type
+                    .examples(spec)
+                    .into_iter()
+                    .find_map(|(_, e)| e.value)
+                {
+                    obj.example = Some(example);
+                }
+                o.payload = Some(obj);
+                o.content_type = Some(content_type.to_owned());
+            }
+            Err(e) => o.payload_error = Some(e),
         }
     }
 }
 
-/// Resolves a `Schema` (inline or `$ref`) down to a concrete `ObjectSchema`.
+/// Resolves a `Schema` (inline or `$ref`) down to a concrete `ObjectSchema`,
+/// with its `allOf` members already merged in.
 pub fn resolve_object_schema(schema: &Schema, spec: &Spec) -> Result<ObjectSchema, String> {
-    match schema.resolve(spec).map_err(|e| e.to_string())? {
+    let obj = match schema.resolve(spec).map_err(|e| e.to_string())? {
         Schema::Object(obj_ref) => match *obj_ref {
-            ObjectOrReference::Object(obj) => Ok(obj),
+            ObjectOrReference::Object(obj) => obj,
             ObjectOrReference::Ref { ref_path, .. } => {
-                Err(format!("unresolved reference: {ref_path}"))
+                return Err(format!("unresolved reference: {ref_path}"));
             }
         },
-        Schema::Boolean(_) => Err("boolean schema is not supported".to_owned()),
+        Schema::Boolean(_) => return Err("boolean schema is not supported".to_owned()),
+    };
+
+    merge_all_of(obj, spec)
+}
+
+/// Folds the `allOf` members into the schema itself, so the properties of a
+/// base schema and of its extensions end up side by side.
+fn merge_all_of(mut obj: ObjectSchema, spec: &Spec) -> Result<ObjectSchema, String> {
+    for member in std::mem::take(&mut obj.all_of) {
+        let member = resolve_object_schema(&member, spec)?;
+        merge_into(&mut obj, member);
     }
+
+    Ok(obj)
+}
+
+/// Merges `other` into `base`. Properties, `required` and object examples are
+/// unioned, with `other` winning on clashes. Both schemas apply, so `enum`
+/// values are intersected and bounds keep the tighter of the two; the other
+/// keywords are only taken from `other` when `base` leaves them unset.
+fn merge_into(base: &mut ObjectSchema, other: ObjectSchema) {
+    base.properties.extend(other.properties);
+    for r in other.required {
+        if !base.required.contains(&r) {
+            base.required.push(r);
+        }
+    }
+
+    base.example = match (base.example.take(), other.example) {
+        (Some(serde_json::Value::Object(mut b)), Some(serde_json::Value::Object(o))) => {
+            b.extend(o);
+            Some(serde_json::Value::Object(b))
+        }
+        (b, o) => o.or(b),
+    };
+
+    base.schema_type = base.schema_type.take().or(other.schema_type);
+    base.items = base.items.take().or(other.items);
+    base.format = base.format.take().or(other.format);
+    base.discriminator = base.discriminator.take().or(other.discriminator);
+    base.one_of.extend(other.one_of);
+    base.any_of.extend(other.any_of);
+
+    base.enum_values = match (base.enum_values.is_empty(), other.enum_values.is_empty()) {
+        (true, _) => other.enum_values,
+        (false, true) => std::mem::take(&mut base.enum_values),
+        (false, false) => std::mem::take(&mut base.enum_values)
+            .into_iter()
+            .filter(|v| other.enum_values.contains(v))
+            .collect(),
+    };
+    base.const_value = base.const_value.take().or(other.const_value);
+    base.pattern = base.pattern.take().or(other.pattern);
+    base.multiple_of = base.multiple_of.take().or(other.multiple_of);
+    base.default = base.default.take().or(other.default);
+    base.unique_items = base.unique_items.take().or(other.unique_items);
+    base.additional_properties = base
+        .additional_properties
+        .take()
+        .or(other.additional_properties);
+    if base.prefix_items.is_empty() {
+        base.prefix_items = other.prefix_items;
+    }
+
+    let lower = |a: &serde_json::Number, b: &serde_json::Number| b.as_f64() < a.as_f64();
+    let higher = |a: &serde_json::Number, b: &serde_json::Number| b.as_f64() > a.as_f64();
+    base.maximum = tighter(base.maximum.take(), other.maximum, lower);
+    base.exclusive_maximum = tighter(
+        base.exclusive_maximum.take(),
+        other.exclusive_maximum,
+        lower,
+    );
+    base.minimum = tighter(base.minimum.take(), other.minimum, higher);
+    base.exclusive_minimum = tighter(
+        base.exclusive_minimum.take(),
+        other.exclusive_minimum,
+        higher,
+    );
+    base.max_length = tighter(base.max_length, other.max_length, |a, b| b < a);
+    base.min_length = tighter(base.min_length, other.min_length, |a, b| b > a);
+    base.max_items = tighter(base.max_items, other.max_items, |a, b| b < a);
+    base.min_items = tighter(base.min_items, other.min_items, |a, b| b > a);
+    base.max_properties = tighter(base.max_properties, other.max_properties, |a, b| b < a);
+    base.min_properties = tighter(base.min_properties, other.min_properties, |a, b| b > a);
+}
+
+/// The tighter of two optional bounds, `b` winning when `tighter_b` says so.
+fn tighter<T>(a: Option<T>, b: Option<T>, tighter_b: impl Fn(&T, &T) -> bool) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if tighter_b(&a, &b) { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Expands `oneOf` (or else `anyOf`) into one schema per alternative, each
+/// merged with what the parent declares next to the composition. The label
+/// is the discriminator value when there is one, then the name of the
+/// referenced schema, then the position. With a `discriminator`, its property
+/// is pinned to the value selecting the alternative. Returns an empty list
+/// when the schema has no alternatives.
+pub fn alternatives(
+    schema: &ObjectSchema,
+    spec: &Spec,
+) -> Result<Vec<(String, ObjectSchema)>, String> {
+    let members = if schema.one_of.is_empty() {
+        &schema.any_of
+    } else {
+        &schema.one_of
+    };
+
+    let mut base = schema.clone();
+    base.one_of.clear();
+    base.any_of.clear();
+    base.discriminator = None;
+
+    let mut alternatives = vec![];
+
+    for (i, member) in members.iter().enumerate() {
+        let ref_path = match member {
+            Schema::Object(obj_ref) => match obj_ref.as_ref() {
+                ObjectOrReference::Ref { ref_path, .. } => Some(ref_path.as_str()),
+                ObjectOrReference::Object(_) => None,
+            },
+            Schema::Boolean(_) => None,
+        };
+        let ref_name = ref_path.and_then(|r| r.rsplit('/').next());
+
+        let mut alt = base.clone();
+        merge_into(&mut alt, resolve_object_schema(member, spec)?);
+
+        let tag = schema.discriminator.as_ref().and_then(|d| {
+            // Mapping targets are either full refs or bare schema names.
+            let mapped = d.mapping.as_ref().and_then(|m| {
+                m.iter()
+                    .find(|(_, target)| {
+                        Some(target.as_str()) == ref_path || Some(target.as_str()) == ref_name
+                    })
+                    .map(|(value, _)| value.clone())
+            });
+            let value = mapped.or_else(|| ref_name.map(str::to_owned))?;
+
+            alt.properties.insert(
+                d.property_name.clone(),
+                Schema::Object(Box::new(ObjectOrReference::Object(ObjectSchema {
+                    schema_type: Some(oas3::spec::SchemaTypeSet::Single(
+                        oas3::spec::SchemaType::String,
+                    )),
+                    const_value: Some(serde_json::Value::String(value.clone())),
+                    example: Some(serde_json::Value::String(value.clone())),
+                    ..ObjectSchema::default()
+                }))),
+            );
+            if !alt.required.contains(&d.property_name) {
+                alt.required.push(d.property_name.clone());
+            }
+
+            Some(value)
+        });
+
+        let label = tag
+            .or_else(|| ref_name.map(str::to_owned))
+            .unwrap_or_else(|| format!("option {}", i + 1));
+        alternatives.push((label, alt));
+    }
+
+    Ok(alternatives)
 }
 
 #[cfg(test)]
@@ -152,6 +436,17 @@ mod tests {
         assert!(f.payload.is_some());
     }
 
+    #[test]
+    fn unresolved_payload_is_reported() {
+        let s = std::include_str!("./testdata/post_login_unresolved_ref.yml");
+        let spec = parse_openapi(s).unwrap();
+        let posts = collect_post(&spec);
+
+        let f = posts.first().unwrap();
+        assert!(f.payload.is_none());
+        assert!(f.payload_error.as_deref().unwrap().contains("Credentials"));
+    }
+
     #[test]
     fn skip_deprecated() {
         {
@@ -271,6 +566,62 @@ mod tests {
         assert_eq!(collect_put(&spec).len(), 1);
     }
 
+    #[test]
+    fn scan_patch() {
+        let s = std::include_str!("./testdata/patch_settings.yml");
+        let spec = parse_openapi(s).unwrap();
+        let patches = collect_patch(&spec);
+        assert_eq!(patches.len(), 1);
+
+        let f = patches.first().unwrap();
+        assert_eq!(f.method, "PATCH");
+        assert_eq!(f.path, "/api/v1/org/settings");
+        assert!(f.payload.is_some());
+        // JSON Merge Patch is drilled as JSON but sent with its own media type
+        assert_eq!(
+            f.content_type.as_deref(),
+            Some("application/merge-patch+json")
+        );
+    }
+
+    #[test]
+    fn scan_delete_with_and_without_body() {
+        let s = std::include_str!("./testdata/delete_members.yml");
+        let spec = parse_openapi(s).unwrap();
+        let deletes = collect_delete(&spec);
+        assert_eq!(deletes.len(), 2);
+
+        let info = deletes
+            .iter()
+            .find(|o| o.path == "/api/v1/org/info")
+            .unwrap();
+        assert_eq!(info.method, "DELETE");
+        assert!(info.payload.is_none());
+        assert!(info.content_type.is_none());
+
+        let members = deletes
+            .iter()
+            .find(|o| o.path == "/api/v1/org/members")
+            .unwrap();
+        assert!(members.payload.is_some());
+        assert_eq!(members.content_type.as_deref(), Some("application/json"));
+    }
+
+    #[test]
+    fn operation_parameters_override_path_level_ones() {
+        let s = std::include_str!("./testdata/get_user_path_params.yml");
+        let spec = parse_openapi(s).unwrap();
+        let gets = collect_gets(&spec);
+
+        let params = &gets.first().unwrap().parameters;
+        // `org` comes from the path item, `id` is declared on both levels and
+        // the operation's `$ref` wins
+        assert_eq!(params.len(), 2);
+        let id = params.iter().find(|p| p.name == "id").unwrap();
+        assert_eq!(id.example, Some(serde_json::json!(42)));
+        assert!(params.iter().any(|p| p.name == "org"));
+    }
+
     #[test]
     fn post_only_spec_has_no_gets() {
         let s = std::include_str!("./testdata/post_login.yml");
@@ -296,15 +647,116 @@ mod tests {
     }
 
     #[test]
-    fn post_payload_with_only_object_level_example_has_no_leaves() {
-        // Properties carry no per-property example, only the object carries one;
-        // the digger consumes per-property examples, so payload still resolves
-        // but yields no usable leaf values downstream.
+    fn post_payload_keeps_object_level_example() {
+        // Properties carry no per-property example, only the object carries
+        // one; it stays on the payload so the digger can seed leaves from it.
         let s = std::include_str!("./testdata/post_login_obj_example.yml");
         let spec = parse_openapi(s).unwrap();
         let posts = collect_post(&spec);
 
         assert_eq!(posts.len(), 1);
-        assert!(posts.first().unwrap().payload.is_some());
+        let payload = posts.first().unwrap().payload.as_ref().unwrap();
+        assert_eq!(payload.example.as_ref().unwrap()["org"], "fiuto");
+    }
+
+    #[test]
+    fn media_type_example_overrides_schema_example() {
+        let s = std::include_str!("./testdata/post_info_media_type_example.yml");
+        let spec = parse_openapi(s).unwrap();
+        let posts = collect_post(&spec);
+
+        let payload = posts.first().unwrap().payload.as_ref().unwrap();
+        assert_eq!(payload.example.as_ref().unwrap()["hq"]["city"], "Verona");
+    }
+
+    #[test]
+    fn all_of_members_are_merged() {
+        let s = std::include_str!("./testdata/post_pets_one_of.yml");
+        let spec = parse_openapi(s).unwrap();
+        let cat = Schema::Object(Box::new(ObjectOrReference::Ref {
+            ref_path: "#/components/schemas/Cat".to_owned(),
+            summary: None,
+            description: None,
+        }));
+
+        let cat = resolve_object_schema(&cat, &spec).unwrap();
+        assert!(cat.all_of.is_empty());
+        assert_eq!(
+            cat.properties.keys().collect::<Vec<_>>(),
+            ["petType", "name", "indoor"]
+        );
+        assert_eq!(cat.required, ["petType", "name", "indoor"]);
+    }
+
+    #[test]
+    fn all_of_members_keep_their_validation_keywords() {
+        let s = std::include_str!("./testdata/post_pets_one_of.yml");
+        let spec = parse_openapi(s).unwrap();
+        let schema: ObjectSchema = serde_yaml_bw::from_str(
+            "type: string\nenum: [a, b, c]\nmaxLength: 8\nminimum: 1\nallOf:\n  - enum: [b, c, d]\n    pattern: '^[a-z]+$'\n    maxLength: 4\n    minLength: 1\n    minimum: 3\n  - additionalProperties: false",
+        )
+        .unwrap();
+
+        let merged = merge_all_of(schema, &spec).unwrap();
+        assert_eq!(
+            merged.enum_values,
+            [serde_json::json!("b"), serde_json::json!("c")]
+        );
+        assert_eq!(merged.pattern.as_deref(), Some("^[a-z]+$"));
+        assert_eq!(merged.max_length, Some(4));
+        assert_eq!(merged.min_length, Some(1));
+        assert_eq!(merged.minimum.and_then(|n| n.as_u64()), Some(3));
+        assert!(merged.additional_properties.is_some());
+    }
+
+    #[test]
+    fn one_of_alternatives_are_pinned_by_the_discriminator() {
+        let s = std::include_str!("./testdata/post_pets_one_of.yml");
+        let spec = parse_openapi(s).unwrap();
+        let posts = collect_post(&spec);
+        let payload = posts.first().unwrap().payload.as_ref().unwrap();
+
+        let alternatives = alternatives(payload, &spec).unwrap();
+        let labels: Vec<_> = alternatives.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, ["cat", "dog"]);
+
+        let (_, dog) = &alternatives[1];
+        let pet_type = resolve_object_schema(&dog.properties["petType"], &spec).unwrap();
+        assert_eq!(pet_type.const_value, Some(serde_json::json!("dog")));
+        assert!(dog.properties.contains_key("bark"));
+        assert!(!dog.properties.contains_key("indoor"));
+    }
+
+    #[test]
+    fn discriminator_mapping_accepts_bare_schema_names() {
+        let s = std::include_str!("./testdata/post_pets_one_of.yml");
+        let spec = parse_openapi(s).unwrap();
+        let schema: ObjectSchema = serde_yaml_bw::from_str(
+            "oneOf:\n  - $ref: '#/components/schemas/Cat'\n  - $ref: '#/components/schemas/Dog'\ndiscriminator:\n  propertyName: petType\n  mapping:\n    cat: Cat\n    dog: Dog",
+        )
+        .unwrap();
+
+        let alternatives = alternatives(&schema, &spec).unwrap();
+        let labels: Vec<_> = alternatives.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(labels, ["cat", "dog"]);
+
+        let (_, cat) = &alternatives[0];
+        let pet_type = resolve_object_schema(&cat.properties["petType"], &spec).unwrap();
+        assert_eq!(pet_type.const_value, Some(serde_json::json!("cat")));
+    }
+
+    #[test]
+    fn any_of_alternatives_without_refs_are_numbered() {
+        let s = std::include_str!("./testdata/single_server.yml");
+        let spec = parse_openapi(s).unwrap();
+        let schema: ObjectSchema =
+            serde_yaml_bw::from_str("anyOf:\n  - type: string\n  - type: integer").unwrap();
+
+        let labels: Vec<_> = alternatives(&schema, &spec)
+            .unwrap()
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+        assert_eq!(labels, ["option 1", "option 2"]);
     }
 }
@@ -1,3 +1,21 @@
+/// Kind tells how a node turns into JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A property carrying its own value.
+    Leaf,
+    /// An object whose properties are the children.
+    Object,
+    /// An array whose single child, named `items`, describes one element. The
+    /// bounds come from `minItems`/`maxItems`.
+    Array {
+        min_items: Option<u64>,
+        max_items: Option<u64>,
+    },
+    /// A `oneOf`/`anyOf` composition whose children are the alternatives,
+    /// named after their label, and only one of them ends up in the payload.
+    Choice,
+}
+
 /// Node is a struct that represents a single property in JSON data.
 /// So this has a name and a possible value or a list of children, for example when you
 /// have a nested object.
@@ -5,6 +23,9 @@
 pub struct Node {
     pub name: String,
     pub value: serde_json::Value,
+    pub kind: Kind,
+    /// Whether the parent object lists this property as `required`.
+    pub required: bool,
     pub parent: Option<std::rc::Weak<std::cell::RefCell<Self>>>,
     pub children: Vec<std::rc::Rc<std::cell::RefCell<Self>>>,
 }
@@ -14,6 +35,8 @@ impl Node {
         std::rc::Rc::new(std::cell::RefCell::new(Self {
             name: name.to_string(),
             value,
+            kind: Kind::Leaf,
+            required: false,
             parent: None,
             children: Vec::new(),
         }))
@@ -39,6 +62,7 @@ pub struct Digger {
 impl Digger {
     pub fn new() -> Self {
         let root = Node::new("root", serde_json::Value::Null);
+        root.borrow_mut().kind = Kind::Object;
 
         Self {
             root: std::rc::Rc::clone(&root),
@@ -46,8 +70,9 @@ impl Digger {
         }
     }
 
-    fn add_child_and_enter(&mut self, child_name: &str) {
+    fn add_child_and_enter(&mut self, child_name: &str, kind: Kind) {
         let child = Node::new(child_name, serde_json::Value::Null);
+        child.borrow_mut().kind = kind;
         Node::add_child(&self.current, std::rc::Rc::clone(&child));
         self.current = child;
     }
@@ -58,45 +83,190 @@ impl Digger {
             .borrow()
             .parent
             .as_ref()
-            .unwrap()
-            .upgrade()
-            .unwrap();
-        self.current = parent;
+            .and_then(std::rc::Weak::upgrade);
+        // The root has no parent to go back to.
+        if let Some(parent) = parent {
+            self.current = parent;
+        }
     }
 
     /// Walks the properties of an object schema, building the tree. A property
     /// that resolves to an object with its own properties becomes a nested
-    /// level; any other property is a leaf and must carry an example value.
+    /// level and an array becomes a node describing its items; any other
+    /// property is a leaf valued with its own example, then with the matching
+    /// field of the object-level example, and finally with one synthesized
+    /// from its schema. A top-level array schema turns the root into an array
+    /// and a top-level composition turns it into a choice.
     pub fn dig(
         &mut self,
         schema: &oas3::spec::ObjectSchema,
         spec: &oas3::Spec,
+    ) -> Result<(), String> {
+        let alternatives = crate::collector::alternatives(schema, spec)?;
+        if !alternatives.is_empty() {
+            self.root.borrow_mut().kind = Kind::Choice;
+            self.dig_alternatives(&alternatives, schema.example.as_ref(), spec, 1)?;
+            return Ok(());
+        }
+
+        if let Some(items) = array_items(schema) {
+            self.root.borrow_mut().kind = array_kind(schema);
+
+            let item = crate::collector::resolve_object_schema(items, spec)?;
+            let seed = schema.example.as_ref().and_then(|e| e.get(0));
+            if !self.dig_node("items", &item, seed, spec, 1)? {
+                tracing::warn!("No value can be generated for the array items, skipping");
+            }
+
+            return Ok(());
+        }
+
+        self.dig_seeded(schema, schema.example.as_ref(), spec, 1)
+    }
+
+    /// Same as [`dig`](Self::dig) for an object, with `seed` being the example
+    /// of the whole object at the current level and `depth` the level of its
+    /// properties.
+    fn dig_seeded(
+        &mut self,
+        schema: &oas3::spec::ObjectSchema,
+        seed: Option<&serde_json::Value>,
+        spec: &oas3::Spec,
+        depth: usize,
     ) -> Result<(), String> {
         for (name, prop) in &schema.properties {
             let resolved = crate::collector::resolve_object_schema(prop, spec)?;
+            let seeded = seed.and_then(|s| s.get(name));
 
-            if resolved.properties.is_empty() {
-                let Some(v) = resolved.example.clone() else {
-                    // No example means we can't generate a value for this
-                    // property, so skip it instead of failing the whole payload.
-                    tracing::warn!("No example found for property: {name}, skipping");
-                    continue;
-                };
+            if !self.dig_node(name, &resolved, seeded, spec, depth)? {
+                // Nothing to build a value from, so skip the property
+                // instead of failing the whole payload.
+                tracing::warn!("No value can be generated for property: {name}, skipping");
+                continue;
+            }
 
-                let n = Node::new(name, v);
-                n.borrow_mut().parent = Some(std::rc::Rc::downgrade(&self.current));
+            if let Some(node) = self.current.borrow().children.last() {
+                node.borrow_mut().required = schema.required.contains(name);
+            }
+        }
+
+        Ok(())
+    }
 
-                self.current.borrow_mut().children.push(n);
+    /// Adds one node per alternative under the current node, returning how
+    /// many of them could be built.
+    fn dig_alternatives(
+        &mut self,
+        alternatives: &[(String, oas3::spec::ObjectSchema)],
+        seed: Option<&serde_json::Value>,
+        spec: &oas3::Spec,
+        depth: usize,
+    ) -> Result<usize, String> {
+        let mut dug = 0;
+        for (label, alt) in alternatives {
+            if self.dig_node(label, alt, seed, spec, depth)? {
+                dug += 1;
             } else {
-                self.add_child_and_enter(name);
+                tracing::warn!("No value can be generated for alternative: {label}, skipping");
+            }
+        }
+
+        Ok(dug)
+    }
 
-                self.dig(&resolved, spec)?;
+    /// Adds `name` under the current node as a choice, an array, a nested
+    /// object level or a leaf. Returns `false`, leaving the tree untouched,
+    /// when no value can be built for it. Past [`MAX_DEPTH`](crate::MAX_DEPTH) every node is a
+    /// leaf.
+    fn dig_node(
+        &mut self,
+        name: &str,
+        schema: &oas3::spec::ObjectSchema,
+        seed: Option<&serde_json::Value>,
+        spec: &oas3::Spec,
+        depth: usize,
+    ) -> Result<bool, String> {
+        if depth >= crate::MAX_DEPTH {
+            return Ok(self.dig_leaf(name, schema, seed, spec));
+        }
 
-                self.exit_one_level();
+        let alternatives = crate::collector::alternatives(schema, spec)?;
+        if !alternatives.is_empty() {
+            self.add_child_and_enter(name, Kind::Choice);
+            let seed = seed.or(schema.example.as_ref());
+            let dug = self.dig_alternatives(&alternatives, seed, spec, depth + 1)?;
+            self.exit_one_level();
+
+            if dug == 0 {
+                self.current.borrow_mut().children.pop();
             }
+            return Ok(dug > 0);
         }
 
-        Ok(())
+        if let Some(items) = array_items(schema) {
+            let item = crate::collector::resolve_object_schema(items, spec)?;
+            let item_seed = schema.example.as_ref().or(seed).and_then(|e| e.get(0));
+
+            self.add_child_and_enter(name, array_kind(schema));
+            let dug = self.dig_node("items", &item, item_seed, spec, depth + 1)?;
+            self.exit_one_level();
+
+            if !dug {
+                self.current.borrow_mut().children.pop();
+            }
+            return Ok(dug);
+        }
+
+        if !schema.properties.is_empty() {
+            self.add_child_and_enter(name, Kind::Object);
+            self.dig_seeded(schema, seed.or(schema.example.as_ref()), spec, depth + 1)?;
+            self.exit_one_level();
+            return Ok(true);
+        }
+
+        Ok(self.dig_leaf(name, schema, seed, spec))
+    }
+
+    /// Adds `name` under the current node as a leaf valued with its own
+    /// example, the seed or a synthesized value, if any.
+    fn dig_leaf(
+        &self,
+        name: &str,
+        schema: &oas3::spec::ObjectSchema,
+        seed: Option<&serde_json::Value>,
+        spec: &oas3::Spec,
+    ) -> bool {
+        let Some(v) = schema
+            .example
+            .clone()
+            .or_else(|| seed.cloned())
+            .or_else(|| crate::synthesizer::synthesize(schema, spec))
+        else {
+            return false;
+        };
+
+        Node::add_child(&self.current, Node::new(name, v));
+        true
+    }
+}
+
+/// Returns the `items` schema when the schema describes an array.
+fn array_items(schema: &oas3::spec::ObjectSchema) -> Option<&oas3::spec::Schema> {
+    let is_array = schema
+        .schema_type
+        .as_ref()
+        .is_none_or(|t| t.contains(oas3::spec::SchemaType::Array));
+    if is_array {
+        schema.items.as_deref()
+    } else {
+        None
+    }
+}
+
+const fn array_kind(schema: &oas3::spec::ObjectSchema) -> Kind {
+    Kind::Array {
+        min_items: schema.min_items,
+        max_items: schema.max_items,
     }
 }
 
@@ -134,6 +304,21 @@ pub fn load_nested_2() -> std::rc::Rc<std::cell::RefCell<Node>> {
     ))
 }
 
+#[cfg(test)]
+pub fn load_arrays() -> std::rc::Rc<std::cell::RefCell<Node>> {
+    dig_payload(std::include_str!("./testdata/post_org_tags.yml"))
+}
+
+#[cfg(test)]
+pub fn load_array_body() -> std::rc::Rc<std::cell::RefCell<Node>> {
+    dig_payload(std::include_str!("./testdata/post_members_array_body.yml"))
+}
+
+#[cfg(test)]
+pub fn load_one_of() -> std::rc::Rc<std::cell::RefCell<Node>> {
+    dig_payload(std::include_str!("./testdata/post_pets_one_of.yml"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,6 +413,30 @@ mod tests {
         assert!(children.next().is_none());
     }
 
+    #[test]
+    fn recursive_schema_stops_at_max_depth() {
+        let root = dig_payload(std::include_str!("./testdata/post_tree_recursive.yml"));
+
+        // follow root > children > items > children > items ... down to the
+        // node where the recursion gave way to a leaf
+        let mut levels = 0;
+        let mut node = std::rc::Rc::clone(&root);
+        loop {
+            let next = node
+                .borrow()
+                .children
+                .iter()
+                .find(|c| matches!(c.borrow().name.as_str(), "children" | "items"))
+                .map(std::rc::Rc::clone);
+            let Some(next) = next else { break };
+            node = next;
+            levels += 1;
+        }
+
+        assert_eq!(levels, crate::MAX_DEPTH);
+        assert_eq!(node.borrow().kind, Kind::Leaf);
+    }
+
     #[test]
     fn node_creation_and_parent_child_relationship() {
         let parent = Node::new("parent", serde_json::json!("parent_value"));
@@ -296,11 +505,131 @@ mod tests {
     }
 
     #[test]
-    fn property_without_example_is_skipped() {
-        // Only the object-level example is present; leaf properties have none,
-        // so the digger skips every property and the tree stays empty.
+    fn object_level_example_seeds_leaves() {
+        // Leaf properties carry no example, so their values come from the
+        // example given for the whole object.
         let root = dig_payload(std::include_str!("./testdata/post_login_obj_example.yml"));
-        assert_eq!(root.borrow().children.len(), 0);
+        let root = root.borrow();
+        assert_eq!(root.children.len(), 3);
+
+        let password = root
+            .children
+            .iter()
+            .find(|c| c.borrow().name == "password")
+            .expect("password leaf should exist");
+        assert_eq!(password.borrow().value, serde_json::json!("CiaoCiao1234$#"));
+    }
+
+    #[test]
+    fn media_type_example_seeds_nested_leaves() {
+        // The request body carries a named example, which overrides the
+        // schema's one and reaches the nested object's leaves too.
+        let root = dig_payload(std::include_str!(
+            "./testdata/post_info_media_type_example.yml"
+        ));
+        let root = root.borrow();
+
+        let hq = root.children.first().unwrap().borrow();
+        assert_eq!(hq.name, "hq");
+        let city = hq
+            .children
+            .iter()
+            .find(|c| c.borrow().name == "city")
+            .expect("city leaf should exist");
+        assert_eq!(city.borrow().value, serde_json::json!("Verona"));
+    }
+
+    #[test]
+    fn property_without_any_example_is_synthesized() {
+        // No example anywhere, so the values are derived from the schema.
+        let root = dig_payload(std::include_str!("./testdata/post_login_no_examples.yml"));
+        let root = root.borrow();
+        assert_eq!(root.children.len(), 3);
+
+        let email = root
+            .children
+            .iter()
+            .find(|c| c.borrow().name == "email")
+            .expect("email leaf should exist");
+        assert_eq!(email.borrow().value, serde_json::json!("user@example.com"));
+    }
+
+    #[test]
+    fn array_property_becomes_array_node() {
+        let root = dig_payload(std::include_str!("./testdata/post_org_tags.yml"));
+        let root = root.borrow();
+
+        let tags = root
+            .children
+            .iter()
+            .find(|c| c.borrow().name == "tags")
+            .expect("tags node should exist");
+        let tags = tags.borrow();
+        assert_eq!(
+            tags.kind,
+            Kind::Array {
+                min_items: Some(1),
+                max_items: Some(3)
+            }
+        );
+
+        // the item leaf takes the first element of the array example
+        assert_eq!(tags.children.len(), 1);
+        let item = tags.children[0].borrow();
+        assert_eq!(item.name, "items");
+        assert_eq!(item.value, serde_json::json!("rust"));
+    }
+
+    #[test]
+    fn top_level_array_body_turns_root_into_array() {
+        let root = dig_payload(std::include_str!("./testdata/post_members_array_body.yml"));
+        let root = root.borrow();
+        assert!(matches!(root.kind, Kind::Array { .. }));
+
+        // the items are objects, so they become a nested level
+        let item = root.children[0].borrow();
+        assert_eq!(item.kind, Kind::Object);
+        assert_eq!(item.children.len(), 2);
+    }
+
+    #[test]
+    fn top_level_one_of_turns_root_into_choice() {
+        let root = load_one_of();
+        let root = root.borrow();
+        assert_eq!(root.kind, Kind::Choice);
+
+        let names: Vec<_> = root
+            .children
+            .iter()
+            .map(|c| c.borrow().name.clone())
+            .collect();
+        assert_eq!(names, ["cat", "dog"]);
+
+        // each alternative carries the discriminator value selecting it
+        let cat = root.children[0].borrow();
+        assert_eq!(cat.kind, Kind::Object);
+        assert_eq!(cat.children[0].borrow().name, "petType");
+        assert_eq!(cat.children[0].borrow().value, "cat");
+    }
+
+    #[test]
+    fn required_flags_follow_the_parent_schema() {
+        // hq is required, other is optional
+        let root = load_nested_2();
+        let root = root.borrow();
+        let required: Vec<_> = root
+            .children
+            .iter()
+            .map(|c| (c.borrow().name.clone(), c.borrow().required))
+            .collect();
+        assert_eq!(
+            required,
+            vec![("hq".to_owned(), true), ("other".to_owned(), false)]
+        );
+
+        // and so are the properties of hq
+        let hq = root.children[0].borrow();
+        assert!(hq.children.iter().all(|c| c.borrow().required));
     }
 
     #[test]
@@ -336,7 +665,7 @@ mod tests {
             } else if child_borrowed.name == "hq" {
                 found_nested = true;
                 // Nested object should have children
-                assert!(child_borrowed.children.len() > 0);
+                assert!(!child_borrowed.children.is_empty());
             }
         }
 
@@ -0,0 +1,54 @@
+/// Why [`Driller::run`](crate::Driller::run) could not drill the spec.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No base URL was given and the spec declares no `servers`.
+    NoServers,
+    /// The spec cannot be used as written: a server selector matching none of
+    /// its servers, or a path template missing one of its parameters.
+    Spec(String),
+    /// No payload can be generated for the request body of `operation`, like
+    /// `POST /users`, because its schema cannot be resolved.
+    Payload { operation: String, message: String },
+    /// A generated payload cannot be serialized.
+    Serialization(serde_json::Error),
+    /// The HTTP client or a request cannot be built or, with
+    /// [`fail_fast`](crate::Driller::fail_fast), a call failed to go through.
+    Transport(reqwest::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoServers => write!(f, "no servers found in the openapi schema"),
+            Self::Spec(message) => write!(f, "{message}"),
+            Self::Payload { operation, message } => {
+                write!(f, "cannot generate the payload of {operation}: {message}")
+            }
+            Self::Serialization(e) => write!(f, "cannot serialize the payload: {e}"),
+            Self::Transport(e) => write!(f, "transport error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Serialization(e) => Some(e),
+            Self::Transport(e) => Some(e),
+            Self::NoServers | Self::Spec(_) | Self::Payload { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Transport(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serialization(e)
+    }
+}
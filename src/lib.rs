@@ -1,15 +1,125 @@
+mod auth;
 mod collector;
 mod digger;
+mod error;
+mod params;
 mod parser;
+mod ratelimit;
+mod responses;
+mod servers;
 mod shuffler;
+mod synthesizer;
+mod validator;
 
+/// How many levels of a schema are followed when digging, synthesizing or
+/// validating. Guards against self-referencing schemas, which would otherwise
+/// recurse until the stack overflows.
+const MAX_DEPTH: usize = 8;
+
+pub use error::Error;
 pub use parser::parse_openapi;
+pub use shuffler::{Coverage, Mode};
+pub use validator::Violation;
+
+/// Whether the spec accepts the request that was sent, so the status code can
+/// be judged: a valid request should succeed, an invalid one be rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expectation {
+    Valid,
+    Invalid,
+}
+
+/// How a call ended: with a response, or with a transport failure before one
+/// could be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Response,
+    Timeout,
+    /// The connection could not be opened (refused, unreachable, DNS).
+    Connect,
+    Tls,
+    /// The connection was reset or closed before the response was complete.
+    Reset,
+    /// Any other failure to send the request or read the response.
+    Transport,
+}
+
+/// Credentials an auth probe sends to a secured operation, all of which the
+/// server should refuse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthProbe {
+    /// No credentials at all.
+    Anonymous,
+    /// The operation's schemes with garbage secrets, an expired JWT for
+    /// tokens.
+    InvalidCredentials,
+    /// A credential under another `Authorization` scheme than the one
+    /// required.
+    WrongScheme,
+}
+
+/// What [`Driller::run`] drilled.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Report {
+    /// The results of each operation in drilling order, empty for an
+    /// operation that was skipped.
+    pub results: Vec<Vec<CallResult>>,
+    /// The operations whose requests cannot be built from the spec, like
+    /// `POST /users`, with why. The rest of the spec is drilled without them.
+    pub skipped: Vec<(String, Error)>,
+}
+
+/// Longest response body kept in a [`CallResult`], in bytes. The checks run
+/// on the whole body before it is cut.
+const MAX_RECORDED_BODY: usize = 64 * 1024;
 
 #[derive(Debug, serde::Serialize)]
 pub struct CallResult {
+    pub method: String,
+    pub operation_id: Option<String>,
+    /// Which input combination this call sent, like `body 3/8, query 2/2`.
+    pub variant: String,
     pub payload: String,
     pub path: String,
+    pub query: String,
+    /// Headers built from the operation's header and cookie parameters.
+    pub headers: std::collections::BTreeMap<String, String>,
+    /// Every header sent, the `OAuth2` token or Digest answer included, with the
+    /// values of credentials redacted.
+    pub request_headers: std::collections::BTreeMap<String, String>,
+    /// `0` when no response was received, see `outcome`.
     pub status_code: u16,
+    /// Times the call was sent again after a 429 or 503, see
+    /// [`Throttle::retries`].
+    pub retries: u32,
+    pub outcome: Outcome,
+    /// Why the call failed, when `outcome` is not [`Outcome::Response`].
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    pub expected: Expectation,
+    /// Set on the calls checking that the operation refuses bad credentials,
+    /// see [`Driller::auth_check`].
+    pub auth_probe: Option<AuthProbe>,
+    /// The operation's `responses` document neither the code, its range
+    /// (`4XX`) nor a `default`.
+    pub undocumented_status: bool,
+    /// An auth probe was answered with something else than 401 or 403: the
+    /// operation may not enforce its security.
+    pub auth_bypass: bool,
+    /// Response headers, names lowercased and repeated values joined by `, `.
+    pub response_headers: std::collections::BTreeMap<String, String>,
+    /// At most [`MAX_RECORDED_BODY`] bytes of the response body.
+    pub response_body: String,
+    pub response_body_truncated: bool,
+    /// Where the response body breaks the schema documented for its status.
+    pub body_violations: Vec<Violation>,
+    /// Documented response headers missing or invalid, and a `Content-Type`
+    /// the operation does not document for the status.
+    pub response_mismatches: Vec<String>,
 }
 
 /// Controls request pacing so the target API is not flooded into answering
@@ -19,6 +129,13 @@ pub struct CallResult {
 pub struct Throttle {
     pub delay: std::time::Duration,
     pub every: usize,
+    /// At most this many requests per second, spaced out by a token bucket.
+    /// Replaces `delay` and `every` when set.
+    pub rate: Option<f64>,
+    /// How many times a call answered with 429 or 503 is sent again, after
+    /// waiting as long as `Retry-After` or the `RateLimit` headers ask (or an
+    /// exponential backoff when they don't). `0` records the answer as is.
+    pub retries: u32,
 }
 
 impl Default for Throttle {
@@ -26,10 +143,97 @@ impl Default for Throttle {
         Self {
             delay: std::time::Duration::ZERO,
             every: 1,
+            rate: None,
+            retries: 0,
+        }
+    }
+}
+
+/// How the HTTP client shared by every request connects to the server.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Give up on opening a connection after this long.
+    pub connect_timeout: Option<std::time::Duration>,
+    /// Give up on a whole request, from sending it to reading the body, after
+    /// this long. Without one a hanging endpoint stalls the run.
+    pub timeout: Option<std::time::Duration>,
+    /// Reuse connections between requests to the same host.
+    pub keep_alive: bool,
+    /// Speak HTTP/2 from the start, also over plain `http`. Over TLS it is
+    /// negotiated anyway when the server offers it.
+    pub http2: bool,
+    /// Send every request through this proxy, `http://host:port` or similar.
+    pub proxy: Option<String>,
+    /// PEM certificate to trust on top of the system roots, for servers
+    /// signed by a private CA.
+    pub ca_cert: Option<Vec<u8>>,
+    /// Accept any TLS certificate, even invalid or self-signed.
+    pub insecure: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(std::time::Duration::from_secs(10)),
+            timeout: Some(std::time::Duration::from_secs(30)),
+            keep_alive: true,
+            http2: false,
+            proxy: None,
+            ca_cert: None,
+            insecure: false,
         }
     }
 }
 
+impl ClientOptions {
+    fn build(&self) -> Result<reqwest::Client, reqwest::Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if !self.keep_alive {
+            builder = builder.pool_max_idle_per_host(0);
+        }
+        if self.http2 {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some(pem) = &self.ca_cert {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(pem)?);
+        }
+
+        builder
+            .tls_danger_accept_invalid_certs(self.insecure)
+            .build()
+    }
+}
+
+/// The `OAuth2` client fiuto gets tokens as, for the operations requiring an
+/// `OAuth2` scheme without a secret of its own.
+///
+/// The token comes from the scheme's `clientCredentials` flow, or its
+/// `password` flow when `login` is set. It is kept for the whole run, renewed
+/// when it expires and when a server rejects it with a 401.
+#[derive(Debug, Clone, Default)]
+pub struct OAuth2 {
+    pub client_id: String,
+    /// Sent with HTTP Basic to the token endpoint. Public clients without
+    /// one send only their id.
+    pub client_secret: Option<String>,
+    /// Scopes to ask for. When empty, the ones each operation requires.
+    pub scopes: Vec<String>,
+    /// Token endpoint used instead of the flows' `tokenUrl`, like a local
+    /// stand-in of the authorization server.
+    pub token_url: Option<String>,
+    /// Resource owner's user and password, for the `password` flow.
+    pub login: Option<(String, String)>,
+}
+
 /// Drives an `OpenAPI` spec.
 ///
 /// Collects every operation, builds the input combinations and fires them at
@@ -38,8 +242,19 @@ impl Default for Throttle {
 pub struct Driller {
     spec: oas3::Spec,
     base_url: Option<String>,
+    server: Option<String>,
+    server_vars: std::collections::BTreeMap<String, String>,
     jwt: Option<String>,
+    basic: Option<String>,
+    secrets: std::collections::BTreeMap<String, String>,
+    oauth2: Option<OAuth2>,
     throttle: Throttle,
+    client: ClientOptions,
+    shuffle: shuffler::Options,
+    max_requests: Option<usize>,
+    fail_fast: bool,
+    concurrency: usize,
+    auth_check: bool,
 }
 
 impl Driller {
@@ -48,19 +263,47 @@ impl Driller {
         Self {
             spec,
             base_url: None,
+            server: None,
+            server_vars: std::collections::BTreeMap::new(),
             jwt: None,
+            basic: None,
+            secrets: std::collections::BTreeMap::new(),
+            oauth2: None,
             throttle: Throttle::default(),
+            client: ClientOptions::default(),
+            shuffle: shuffler::Options::default(),
+            max_requests: None,
+            fail_fast: false,
+            concurrency: 1,
+            auth_check: false,
         }
     }
 
-    /// Override the server base URL. Takes precedence over the one declared in
-    /// the spec.
+    /// Override the server base URL. Takes precedence over the ones declared
+    /// in the spec, for every operation.
     #[must_use]
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
         self
     }
 
+    /// Which of the spec's `servers` to use instead of the first one: its
+    /// index in the list, or its description. Operations and paths declaring
+    /// their own `servers` use the matching one, if any.
+    #[must_use]
+    pub fn server(mut self, selector: impl Into<String>) -> Self {
+        self.server = Some(selector.into());
+        self
+    }
+
+    /// Value of a server URL variable, like `region` in
+    /// `https://{region}.api.example.com`, instead of its `default`.
+    #[must_use]
+    pub fn server_var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.server_vars.insert(name.into(), value.into());
+        self
+    }
+
     /// Bearer token sent on endpoints that declare a matching security scheme.
     #[must_use]
     pub fn jwt(mut self, jwt: impl Into<String>) -> Self {
@@ -68,6 +311,34 @@ impl Driller {
         self
     }
 
+    /// User and password sent on endpoints that declare an HTTP basic scheme.
+    #[must_use]
+    pub fn basic(mut self, user: impl AsRef<str>, password: impl AsRef<str>) -> Self {
+        self.basic = Some(format!("{}:{}", user.as_ref(), password.as_ref()));
+        self
+    }
+
+    /// Secret for the security scheme named `scheme` in the spec, sent on the
+    /// operations requiring it where the scheme says: an API key in its
+    /// header, query parameter or cookie, or an `Authorization` header for
+    /// HTTP schemes. Basic and Digest take `user:password`, answering the
+    /// Digest challenge when the server sends it; other HTTP schemes take
+    /// their token.
+    #[must_use]
+    pub fn auth(mut self, scheme: impl Into<String>, secret: impl Into<String>) -> Self {
+        self.secrets.insert(scheme.into(), secret.into());
+        self
+    }
+
+    /// Client getting the tokens of `OAuth2` schemes from their token endpoint.
+    /// A secret given with [`auth`](Driller::auth) is sent as the token
+    /// instead.
+    #[must_use]
+    pub fn oauth2(mut self, oauth2: OAuth2) -> Self {
+        self.oauth2 = Some(oauth2);
+        self
+    }
+
     /// Space out requests to avoid hitting rate limits.
     #[must_use]
     pub const fn throttle(mut self, throttle: Throttle) -> Self {
@@ -75,58 +346,188 @@ impl Driller {
         self
     }
 
-    /// Execute all operations in the spec.
+    /// Timeouts, connection reuse, proxy and TLS settings of the client
+    /// sending every request.
+    #[must_use]
+    pub fn client(mut self, client: ClientOptions) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Which body payloads to send: every combination, only valid ones or
+    /// the negative cases missing one required property.
+    #[must_use]
+    pub const fn mode(mut self, mode: Mode) -> Self {
+        self.shuffle.mode = mode;
+        self
+    }
+
+    /// How many property combinations to send: all of them, or only enough
+    /// to cover every interaction of a few properties.
+    #[must_use]
+    pub const fn coverage(mut self, coverage: Coverage) -> Self {
+        self.shuffle.coverage = coverage;
+        self
+    }
+
+    /// Hard cap on the requests sent for a single operation, the
+    /// [`auth_check`](Driller::auth_check) probes included. The ones past it
+    /// are skipped.
+    #[must_use]
+    pub const fn max_requests_per_operation(mut self, max: usize) -> Self {
+        self.max_requests = Some(max);
+        self
+    }
+
+    /// Stop the run at the first transport failure instead of recording it
+    /// and moving on.
+    #[must_use]
+    pub const fn fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// How many requests may be in flight at once, across and within
+    /// operations. Results keep the order of a sequential run.
+    #[must_use]
+    pub const fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Also probe every secured operation with no credentials, invalid ones
+    /// and a credential of the wrong scheme, flagging the probes the server
+    /// does not refuse with a 401 or 403, see [`CallResult::auth_bypass`].
+    #[must_use]
+    pub const fn auth_check(mut self, auth_check: bool) -> Self {
+        self.auth_check = auth_check;
+        self
+    }
+
+    /// Execute all operations in the spec. An operation whose requests cannot
+    /// be built from the spec is skipped and listed in [`Report::skipped`].
     ///
     /// # Errors
-    /// Returns the first request error encountered, stopping the run.
-    pub async fn run(self) -> Result<Vec<Vec<CallResult>>, reqwest::Error> {
+    /// Returns an [`Error`] when no server can be picked or the client cannot
+    /// be built, and with [`fail_fast`](Driller::fail_fast) at the first
+    /// transport failure.
+    pub async fn run(self) -> Result<Report, Error> {
         let Self {
             spec,
             base_url,
+            server,
+            server_vars,
             jwt,
+            basic,
+            mut secrets,
+            oauth2,
             throttle,
+            client,
+            shuffle,
+            max_requests,
+            fail_fast,
+            concurrency,
+            auth_check,
         } = self;
 
         tracing::info!("openapi version: {}", spec.openapi);
 
-        // NOTE: url passed in the command line takes precedence over the one in the openapi schema
-        let base_url = base_url.unwrap_or_else(|| retrieve_base_url(&spec));
-        let jwt_name = get_jwt_token(&spec);
+        // NOTE: url passed in the command line takes precedence over the ones in the openapi schema
+        let overridden = base_url.is_some();
+        let base_url = match base_url {
+            Some(url) => url,
+            None => retrieve_base_url(&spec, (server.as_deref(), &server_vars))?,
+        };
+        // The JWT and the basic login are the secrets of every bearer and
+        // basic scheme, unless given for a scheme explicitly.
+        for (scheme, secret) in [("bearer", jwt), ("basic", basic)] {
+            let Some(secret) = secret else { continue };
+            for name in auth::http_schemes(&spec, scheme) {
+                secrets.entry(name).or_insert_with(|| secret.clone());
+            }
+        }
 
         let posts = collector::collect_post(&spec);
         let puts = collector::collect_put(&spec);
+        let patches = collector::collect_patch(&spec);
+        let deletes = collector::collect_delete(&spec);
         let gets = collector::collect_gets(&spec);
 
         let mut operations = vec![];
         operations.extend_from_slice(gets.as_slice());
         operations.extend_from_slice(posts.as_slice());
         operations.extend_from_slice(puts.as_slice());
+        operations.extend_from_slice(patches.as_slice());
+        operations.extend_from_slice(deletes.as_slice());
 
-        let mut all_results = vec![];
+        // One client for the whole run, so connections are pooled and reused.
+        let client = client.build().map_err(|e| {
+            tracing::error!("Error building the HTTP client: {:?}", e);
+            e
+        })?;
+
+        // Operations are planned one at a time as the calls before them go
+        // out, and each result is checked and trimmed as soon as it comes
+        // back, so neither every request nor every full body is held at once.
+        // Calls of different operations can still be in flight together.
+        // Going by index keeps the closure free of borrowed arguments, which
+        // would make the future of `run` lose `Send`.
+        let mut skipped = vec![];
+        let planned = (0..operations.len()).flat_map(|i| {
+            let p = &operations[i];
+            let op_url = if overridden {
+                None
+            } else {
+                servers::for_operation(&p.servers, (server.as_deref(), &server_vars))
+            };
+            let op_calls = plan_operation(
+                &spec,
+                &client,
+                p.clone(),
+                op_url.as_deref().unwrap_or(&base_url),
+                (&secrets, oauth2.as_ref()),
+                (shuffle, max_requests.unwrap_or(usize::MAX), auth_check),
+            )
+            // Skipping the broken operation lets the rest of the spec be
+            // drilled.
+            .unwrap_or_else(|e| {
+                tracing::warn!("Skipping {} {}: {e}", p.method, p.path);
+                skipped.push((format!("{} {}", p.method, p.path), e));
+                vec![]
+            });
+            op_calls.into_iter().map(move |c| (i, c))
+        });
+        let finish = |i: usize, r: &mut CallResult| {
+            let p: &collector::Op = &operations[i];
+            responses::check(&p.operation, &spec, r);
+            r.operation_id.clone_from(&p.operation.operation_id);
+            r.auth_bypass = r.auth_probe.is_some()
+                && r.outcome == Outcome::Response
+                && !matches!(r.status_code, 401 | 403);
+            cap_body(r);
+        };
 
         // Shared across operations so `every` counts requests globally instead
         // of restarting the count for each endpoint.
         let mut pacer = Pacer::new(throttle);
+        let tokens = auth::Tokens::new(oauth2.clone());
+        let results = dispatch(
+            (&client, &tokens),
+            planned,
+            (concurrency, fail_fast),
+            (&mut pacer, finish),
+        )
+        .await?;
 
-        for p in operations {
-            let result = exec_operation(
-                &spec,
-                p.clone(),
-                &base_url,
-                (jwt_name.clone(), jwt.clone()),
-                &mut pacer,
-            )
-            .await;
-            match result {
-                Ok(r) => all_results.push(r),
-                Err(e) => {
-                    tracing::error!("Error executing operation: {:?}", e);
-                    return Err(e);
-                }
-            }
+        let mut all_results: Vec<Vec<CallResult>> = operations.iter().map(|_| vec![]).collect();
+        for (i, r) in results {
+            all_results[i].push(r);
         }
 
-        Ok(all_results)
+        Ok(Report {
+            results: all_results,
+            skipped,
+        })
     }
 }
 
@@ -136,200 +537,857 @@ impl Driller {
 struct Pacer {
     throttle: Throttle,
     sent: usize,
+    /// The token bucket holds a single token, so requests are evenly spaced
+    /// rather than sent in bursts.
+    tokens: f64,
+    refilled: tokio::time::Instant,
+    /// Set when the server asked to wait, holding back every request.
+    resume_at: Option<tokio::time::Instant>,
 }
 
 impl Pacer {
-    const fn new(throttle: Throttle) -> Self {
-        Self { throttle, sent: 0 }
+    fn new(throttle: Throttle) -> Self {
+        Self {
+            throttle,
+            sent: 0,
+            tokens: 1.0,
+            refilled: tokio::time::Instant::now(),
+            resume_at: None,
+        }
     }
 
-    /// Pause before a request once a full group of `every` requests has already
+    /// Wait out a pause the server asked for, then either take a token from
+    /// the bucket or pause once a full group of `every` requests has already
     /// gone out, so neither the first nor the last request waits for nothing.
     async fn before_request(&mut self) {
-        let every = self.throttle.every.max(1);
-        if self.sent > 0 && !self.throttle.delay.is_zero() && self.sent.is_multiple_of(every) {
-            tokio::time::sleep(self.throttle.delay).await;
+        if let Some(at) = self.resume_at.take() {
+            tokio::time::sleep_until(at).await;
+        }
+
+        if let Some(rate) = self.throttle.rate.filter(|r| *r > 0.0) {
+            let now = tokio::time::Instant::now();
+            let earned = now.duration_since(self.refilled).as_secs_f64() * rate;
+            self.tokens = (self.tokens + earned).min(1.0);
+            self.refilled = now;
+            if self.tokens < 1.0 {
+                let missing = (1.0 - self.tokens) / rate;
+                tokio::time::sleep(std::time::Duration::from_secs_f64(missing)).await;
+                self.refilled = tokio::time::Instant::now();
+            }
+            self.tokens = 0.0;
+        } else {
+            let every = self.throttle.every.max(1);
+            if self.sent > 0 && !self.throttle.delay.is_zero() && self.sent.is_multiple_of(every) {
+                tokio::time::sleep(self.throttle.delay).await;
+            }
         }
         self.sent += 1;
     }
-}
 
-fn get_jwt_token(spec: &oas3::Spec) -> Option<String> {
-    let components = spec.components.as_ref()?;
+    /// Hold back every request, not only the retried one, for `wait`.
+    fn pause(&mut self, wait: std::time::Duration) {
+        let at = tokio::time::Instant::now() + wait;
+        self.resume_at = Some(self.resume_at.map_or(at, |current| current.max(at)));
+    }
+}
 
-    components.security_schemes.iter().find_map(|(k, v)| {
-        // Security schemes can be a $ref, but a bearer scheme is always inline
-        // in practice, so only inline ones are inspected.
-        let oas3::spec::ObjectOrReference::Object(scheme) = v else {
-            return None;
-        };
+/// A request ready to be sent, with what its result records about the input.
+struct Call {
+    request: reqwest::Request,
+    variant: String,
+    payload: String,
+    path: String,
+    /// The query string as recorded, credentials redacted.
+    query: String,
+    /// Headers whose values are redacted when recording the request.
+    secret_headers: Vec<String>,
+    headers: std::collections::BTreeMap<String, String>,
+    expected: Expectation,
+    /// Login answering a Digest challenge, if the server sends one.
+    digest: Option<(String, String)>,
+    /// How to get the `OAuth2` token sent with the request.
+    oauth2: Option<auth::Grant>,
+    probe: Option<AuthProbe>,
+}
 
-        match scheme {
-            oas3::spec::SecurityScheme::Http { scheme, .. }
-                if scheme.eq_ignore_ascii_case("bearer") =>
-            {
-                Some(k.clone())
-            }
+impl Call {
+    /// Wraps a request, recording what it sends without the secrets of
+    /// `credentials`. The rest defaults to a bodyless, expected-valid call.
+    fn new(request: reqwest::Request, credentials: &[auth::Credential]) -> Self {
+        let (secret_headers, secret_query) = auth::secret_names(credentials);
+        let query = request
+            .url()
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if secret_query.iter().any(|s| s == name) => {
+                    format!("{name}=<redacted>")
+                }
+                _ => pair.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let digest = credentials.iter().find_map(|c| match c {
+            auth::Credential::Digest(user, password) => Some((user.clone(), password.clone())),
             _ => None,
+        });
+        let oauth2 = credentials.iter().find_map(|c| match c {
+            auth::Credential::OAuth2(grant) => Some(grant.clone()),
+            _ => None,
+        });
+
+        Self {
+            request,
+            variant: String::new(),
+            payload: String::new(),
+            path: String::new(),
+            query,
+            secret_headers,
+            headers: std::collections::BTreeMap::new(),
+            expected: Expectation::Valid,
+            digest,
+            oauth2,
+            probe: None,
         }
-    })
+    }
+
+    fn try_clone(&self) -> Option<Self> {
+        Some(Self {
+            request: self.request.try_clone()?,
+            variant: self.variant.clone(),
+            payload: self.payload.clone(),
+            path: self.path.clone(),
+            query: self.query.clone(),
+            secret_headers: self.secret_headers.clone(),
+            headers: self.headers.clone(),
+            expected: self.expected,
+            digest: self.digest.clone(),
+            oauth2: self.oauth2.clone(),
+            probe: self.probe,
+        })
+    }
 }
 
-async fn exec_operation(
+/// Builds every request drilling an operation, without sending any.
+fn plan_operation(
     spec: &oas3::Spec,
+    client: &reqwest::Client,
     op: collector::Op,
     base_url: &str,
-    (jwt_name, jwt): (Option<String>, Option<String>),
-    pacer: &mut Pacer,
-) -> Result<Vec<CallResult>, reqwest::Error> {
-    // An operation without its own `security` inherits the spec-level requirement
-    let security = if op.operation.security.is_empty() {
-        &spec.security
-    } else {
-        &op.operation.security
-    };
+    (secrets, oauth2): (&std::collections::BTreeMap<String, String>, Option<&OAuth2>),
+    (shuffle, max_requests, auth_check): (shuffler::Options, usize, bool),
+) -> Result<Vec<Call>, Error> {
+    let security = auth::effective(&op.operation, spec);
+    let credentials = auth::for_requirements(security, spec, secrets, oauth2);
 
-    match op.method.as_str() {
-        "GET" => drill_get_endpoint(base_url, &op.path, (jwt_name, jwt), security, pacer).await,
-        "POST" | "PUT" => {
-            let Some(s) = op.payload else {
-                tracing::warn!("No payload found for {} {}", op.method, op.path);
-                return Ok(vec![]);
-            };
+    let method = match op.method.as_str() {
+        "GET" => reqwest::Method::GET,
+        "POST" => reqwest::Method::POST,
+        "PUT" => reqwest::Method::PUT,
+        "PATCH" => reqwest::Method::PATCH,
+        "DELETE" => reqwest::Method::DELETE,
+        _ => {
+            tracing::warn!("Unsupported method: {}", op.method);
+            return Ok(vec![]);
+        }
+    };
 
-            // POST and PUT drill the same way, only the verb differs.
-            let method = if op.method == "PUT" {
-                reqwest::Method::PUT
+    let path = params::expand_path(&op.path, &op.parameters, spec).map_err(|e| {
+        Error::Spec(format!(
+            "cannot expand path for {} {}: {e}",
+            op.method, op.path
+        ))
+    })?;
+    // Inputs are generated lazily up to one past the cap, so running into it
+    // is known without building every combination.
+    let cap = max_requests.saturating_add(1);
+    // One URL per query string variant; the body combinations are fired
+    // against each of them.
+    let urls: Vec<String> = params::query_combinations(&op.parameters, spec)
+        .take(cap)
+        .map(|q| {
+            if q.is_empty() {
+                format!("{base_url}{path}")
             } else {
-                reqwest::Method::POST
-            };
+                format!("{base_url}{path}?{q}")
+            }
+        })
+        .collect();
+    let header_sets: Vec<params::HeaderSet> = params::header_combinations(&op.parameters, spec)
+        .take(cap)
+        .collect();
+
+    let operation = || format!("{} {}", op.method, op.path);
+    if let Some(message) = op.payload_error {
+        return Err(Error::Payload {
+            operation: operation(),
+            message,
+        });
+    }
+    let body = match (op.payload, op.content_type) {
+        (Some(s), Some(content_type)) => {
+            let mut digger = digger::Digger::new();
+            digger.dig(&s, spec).map_err(|message| Error::Payload {
+                operation: operation(),
+                message,
+            })?;
 
-            let url = format!("{base_url}{}", op.path);
-            drill_body_endpoint(method, url, &s, (jwt_name, jwt), security, spec, pacer).await
+            let payloads = shuffler::payloads(&digger.root, shuffle, cap)
+                .map(|p| {
+                    let expected = if shuffler::is_valid(&digger.root, &p) {
+                        Expectation::Valid
+                    } else {
+                        Expectation::Invalid
+                    };
+                    (p, expected)
+                })
+                .collect::<Vec<_>>();
+            Some((payloads, content_type))
         }
+        // GET never carries a body and a DELETE body is optional, so both are
+        // fired bare. The other verbs need a payload to drill.
+        _ if method == reqwest::Method::GET || method == reqwest::Method::DELETE => None,
         _ => {
-            tracing::warn!("Unsupported method: {}", op.method);
-            Ok(vec![])
+            tracing::warn!("No payload found for {} {}", op.method, op.path);
+            return Ok(vec![]);
+        }
+    };
+    let body = body
+        .as_ref()
+        .map(|(payloads, content_type)| (payloads.as_slice(), content_type.as_str()));
+
+    drill_calls(
+        (&method, client),
+        (&urls, &header_sets),
+        body,
+        (spec, security, &credentials),
+        (max_requests, auth_check),
+    )
+}
+
+/// Builds the calls drilling an operation with its inputs: with the
+/// credentials at hand, without any when its auth is optional, then the auth
+/// probes, `max_requests` of them at most.
+fn drill_calls(
+    (method, client): (&reqwest::Method, &reqwest::Client),
+    inputs: (&[String], &[params::HeaderSet]),
+    body: Option<(&[(serde_json::Value, Expectation)], &str)>,
+    (spec, security, credentials): (
+        &oas3::Spec,
+        &[oas3::spec::SecurityRequirement],
+        &[auth::Credential],
+    ),
+    (max_requests, auth_check): (usize, bool),
+) -> Result<Vec<Call>, Error> {
+    // The auth probes count against the cap too. Room is kept for them, but
+    // never all of it, so a capped operation is still drilled and probed.
+    let mut probes = if auth_check {
+        auth::probes(security, spec, credentials)
+    } else {
+        vec![]
+    };
+    let budget = max_requests
+        .saturating_sub(probes.len())
+        .max(max_requests.min(1));
+
+    let mut calls = operation_calls(method, client, inputs, body, credentials, budget)?;
+    // With optional auth the operation answers callers without credentials
+    // too, maybe differently, so it is drilled both ways.
+    let left = budget.saturating_sub(calls.len());
+    if !credentials.is_empty() && auth::allows_anonymous(security) && left > 0 {
+        let anonymous = operation_calls(method, client, inputs, body, &[], left)?;
+        calls.extend(anonymous.into_iter().map(|call| Call {
+            variant: if call.variant == "single" {
+                "without credentials".to_owned()
+            } else {
+                format!("{}, without credentials", call.variant)
+            },
+            ..call
+        }));
+    }
+    if !probes.is_empty() {
+        probes.truncate(max_requests.saturating_sub(calls.len()));
+        calls.extend(probe_calls(method, client, inputs, body, probes)?);
+    }
+    Ok(calls)
+}
+
+/// Builds the calls of an operation, one per payload and input combination
+/// when it takes a body.
+fn operation_calls(
+    method: &reqwest::Method,
+    client: &reqwest::Client,
+    inputs: (&[String], &[params::HeaderSet]),
+    body: Option<(&[(serde_json::Value, Expectation)], &str)>,
+    credentials: &[auth::Credential],
+    max_requests: usize,
+) -> Result<Vec<Call>, Error> {
+    // Every body-carrying verb drills the same way, only the verb differs.
+    body.map_or_else(
+        || bodyless_calls(method, client, inputs, credentials, max_requests),
+        |body| body_calls(method, client, inputs, body, credentials, max_requests),
+    )
+}
+
+/// Builds the auth probes of an operation: its first valid request, sent
+/// with the credentials of each probe instead of the real ones.
+fn probe_calls(
+    method: &reqwest::Method,
+    client: &reqwest::Client,
+    (urls, header_sets): (&[String], &[params::HeaderSet]),
+    body: Option<(&[(serde_json::Value, Expectation)], &str)>,
+    probes: Vec<(AuthProbe, Vec<auth::Credential>)>,
+) -> Result<Vec<Call>, Error> {
+    let inputs = (
+        &urls[..urls.len().min(1)],
+        &header_sets[..header_sets.len().min(1)],
+    );
+    let body = body.map(|(payloads, content_type)| {
+        let first = payloads
+            .iter()
+            .position(|(_, expected)| *expected == Expectation::Valid)
+            .unwrap_or(0);
+        (
+            &payloads[first..payloads.len().min(first + 1)],
+            content_type,
+        )
+    });
+
+    let mut calls = vec![];
+    for (probe, credentials) in probes {
+        let label = match probe {
+            AuthProbe::Anonymous => "no credentials",
+            AuthProbe::InvalidCredentials => "invalid credentials",
+            AuthProbe::WrongScheme => "wrong scheme",
+        };
+        for call in operation_calls(method, client, inputs, body, &credentials, 1)? {
+            calls.push(Call {
+                variant: format!("auth probe, {label}"),
+                expected: Expectation::Invalid,
+                probe: Some(probe),
+                ..call
+            });
         }
     }
+    Ok(calls)
 }
 
-async fn drill_get_endpoint(
-    base_url: &str,
-    path: &str,
-    (jwt_name, jwt): (Option<String>, Option<String>),
-    security: &[oas3::spec::SecurityRequirement],
-    pacer: &mut Pacer,
-) -> Result<Vec<CallResult>, reqwest::Error> {
-    let url = format!("{base_url}{path}");
-
-    tracing::info!("GET URL: {}", url);
-
-    let client = reqwest::Client::new();
-    let mut req = client.request(reqwest::Method::GET, url.clone());
-
-    if let (Some(jwt_name), Some(jwt)) = (jwt_name.as_ref(), jwt.as_ref()) {
-        for ss in security {
-            for k in ss.0.keys() {
-                if k == jwt_name {
-                    req = req.header("Authorization", format!("Bearer {jwt}"));
-                }
+/// Builds one request without a body per URL and header set, used for GET
+/// and for DELETE operations that declare no request body. Required
+/// parameters are always sent, so every request is expected to be valid.
+/// Stops after `max_requests` requests.
+fn bodyless_calls(
+    method: &reqwest::Method,
+    client: &reqwest::Client,
+    (urls, header_sets): (&[String], &[params::HeaderSet]),
+    credentials: &[auth::Credential],
+    max_requests: usize,
+) -> Result<Vec<Call>, Error> {
+    let mut calls = vec![];
+
+    for ((u, url), (h, headers)) in urls
+        .iter()
+        .enumerate()
+        .flat_map(|u| header_sets.iter().enumerate().map(move |h| (u, h)))
+    {
+        if calls.len() == max_requests {
+            warn_capped(method, max_requests);
+            break;
+        }
+
+        tracing::info!("{method} URL: {}", url);
+
+        let req = client.request(method.clone(), auth::with_query(url, credentials));
+        let r = auth::apply(req, headers, credentials)
+            .build()
+            .map_err(|e| {
+                tracing::error!("Error building request: {:?}", e);
+                e
+            })?;
+
+        calls.push(Call {
+            variant: variant_label(&[("query", u, urls.len()), ("headers", h, header_sets.len())]),
+            path: url.clone(),
+            headers: headers.iter().cloned().collect(),
+            ..Call::new(r, credentials)
+        });
+    }
+
+    Ok(calls)
+}
+
+/// Builds a request for every payload against each URL and header set,
+/// stopping after `max_requests` requests.
+fn body_calls(
+    method: &reqwest::Method,
+    client: &reqwest::Client,
+    (urls, header_sets): (&[String], &[params::HeaderSet]),
+    (payloads, content_type): (&[(serde_json::Value, Expectation)], &str),
+    credentials: &[auth::Credential],
+    max_requests: usize,
+) -> Result<Vec<Call>, Error> {
+    let mut calls = vec![];
+
+    'payloads: for (p, (pp, expected)) in payloads.iter().enumerate() {
+        let s = serde_json::to_string(pp)?;
+
+        tracing::info!("Payload: {}", s);
+
+        for ((u, url), (h, headers)) in urls
+            .iter()
+            .enumerate()
+            .flat_map(|u| header_sets.iter().enumerate().map(move |h| (u, h)))
+        {
+            if calls.len() == max_requests {
+                warn_capped(method, max_requests);
+                break 'payloads;
             }
+
+            let req = client
+                .request(method.clone(), auth::with_query(url, credentials))
+                .body(s.clone())
+                .header("Content-Type", content_type);
+            let r = auth::apply(req, headers, credentials)
+                .build()
+                .map_err(|e| {
+                    tracing::error!("Error building request: {:?}", e);
+                    e
+                })?;
+
+            calls.push(Call {
+                variant: variant_label(&[
+                    ("body", p, payloads.len()),
+                    ("query", u, urls.len()),
+                    ("headers", h, header_sets.len()),
+                ]),
+                payload: s.clone(),
+                path: url.clone(),
+                headers: headers.iter().cloned().collect(),
+                expected: *expected,
+                ..Call::new(r, credentials)
+            });
         }
     }
 
-    let r = req.build().map_err(|e| {
-        tracing::error!("Error building request: {:?}", e);
-        e
-    })?;
-    pacer.before_request().await;
-    let resp = client.execute(r).await?;
-
-    Ok(vec![CallResult {
-        payload: String::new(),
-        path: url.clone(),
-        status_code: resp.status().as_u16(),
-    }])
-}
-
-async fn drill_body_endpoint(
-    method: reqwest::Method,
-    url: String,
-    payload: &oas3::spec::ObjectSchema,
-    (jwt_name, jwt): (Option<String>, Option<String>),
-    security: &[oas3::spec::SecurityRequirement],
-    spec: &oas3::Spec,
-    pacer: &mut Pacer,
-) -> Result<Vec<CallResult>, reqwest::Error> {
-    let client = reqwest::Client::new();
+    Ok(calls)
+}
 
-    let mut responses = vec![];
+/// Sends the calls, tagged with the index of their operation, with at most
+/// `concurrency` of them in flight, and returns their results in the order of
+/// `calls`. Calls are only pulled from `calls` as they are about to be sent,
+/// and `finish` runs on each result as soon as it comes back. The pacer is
+/// shared by all of them, so the throttle spaces out requests globally rather
+/// than per worker.
+async fn dispatch(
+    (client, tokens): (&reqwest::Client, &auth::Tokens),
+    calls: impl Iterator<Item = (usize, Call)> + Send,
+    (concurrency, fail_fast): (usize, bool),
+    (pacer, finish): (&mut Pacer, impl Fn(usize, &mut CallResult) + Sync),
+) -> Result<Vec<(usize, CallResult)>, reqwest::Error> {
+    use futures_util::StreamExt;
 
-    let mut prop_combinations = {
-        let mut digger = digger::Digger::new();
-        let root = digger.dig(payload, spec);
-        if let Err(e) = root {
-            tracing::error!("Error digging the payload: {:?}", e);
-            return Ok(vec![]);
+    let mut results = vec![];
+    let pacer = tokio::sync::Mutex::new(pacer);
+    let finish = &finish;
+    let mut sent = futures_util::stream::iter(calls)
+        .map(|(i, call)| {
+            let pacer = &pacer;
+            async move {
+                let mut r = send_paced((client, tokens), call, fail_fast, pacer).await?;
+                finish(i, &mut r);
+                Ok((i, r))
+            }
+        })
+        .buffered(concurrency.max(1));
+
+    while let Some(result) = sent.next().await {
+        match result {
+            Ok(r) => results.push(r),
+            Err(e) => {
+                tracing::error!("Error executing operation: {:?}", e);
+                return Err(e);
+            }
         }
+    }
 
-        shuffler::do_it(&digger.root)
-    };
+    Ok(results)
+}
+
+/// Sends a call when the pacer allows it, and again while the server answers
+/// that it is rate limited and retries are left.
+async fn send_paced(
+    (client, tokens): (&reqwest::Client, &auth::Tokens),
+    mut call: Call,
+    fail_fast: bool,
+    pacer: &tokio::sync::Mutex<&mut Pacer>,
+) -> Result<CallResult, reqwest::Error> {
+    let retries = pacer.lock().await.throttle.retries;
 
-    // add empty payload
-    prop_combinations.push(std::collections::HashMap::new());
+    let mut attempt = 0;
+    loop {
+        // A body can only be sent twice if it was cloned beforehand.
+        let again = if attempt < retries {
+            call.try_clone()
+        } else {
+            None
+        };
 
-    for pp in prop_combinations {
-        let s = serde_json::to_string(&pp).unwrap(); // TODO: handle the error
+        // Waiting with the lock held makes the other calls wait too.
+        pacer.lock().await.before_request().await;
+        let mut result = execute((client, tokens), call, fail_fast).await?;
+        result.retries = attempt;
 
-        tracing::info!("Payload: {}", s);
+        if let Some(wait) = ratelimit::exhausted_for(&result.response_headers) {
+            pacer.lock().await.pause(wait);
+        }
 
-        let mut req = client
-            .request(method.clone(), url.clone())
-            .body(s.clone())
-            .header("Content-Type", "application/json"); // TODO: Make this configurable
-
-        if let (Some(jwt_name), Some(jwt)) = (jwt_name.as_ref(), jwt.as_ref()) {
-            for ss in security {
-                for k in ss.0.keys() {
-                    if k == jwt_name {
-                        req = req.header("Authorization", format!("Bearer {jwt}"));
-                    }
-                }
+        match again {
+            Some(next) if ratelimit::is_rate_limited(result.status_code) => {
+                let wait =
+                    ratelimit::retry_after(&result.response_headers, std::time::SystemTime::now())
+                        .unwrap_or_else(|| ratelimit::backoff(attempt));
+                tracing::warn!(
+                    "{} {} answered {}, retrying in {wait:?}",
+                    result.method,
+                    result.path,
+                    result.status_code
+                );
+                pacer.lock().await.pause(wait);
+                call = next;
+                attempt += 1;
             }
+            _ => return Ok(result),
         }
+    }
+}
 
-        let r = req.build().unwrap(); // TODO: handle the error
-        pacer.before_request().await;
-        let resp = client.execute(r).await?;
+/// Sends a request and records the exchange, with the request headers that
+/// actually went out once authenticated. What depends on the spec is left for
+/// the caller to fill.
+///
+/// A transport failure is recorded as the call's outcome, or returned when
+/// `fail_fast` is set.
+async fn execute(
+    (client, tokens): (&reqwest::Client, &auth::Tokens),
+    call: Call,
+    fail_fast: bool,
+) -> Result<CallResult, reqwest::Error> {
+    let Call {
+        request,
+        variant,
+        payload,
+        path,
+        query,
+        secret_headers,
+        headers,
+        expected,
+        digest,
+        oauth2,
+        probe,
+    } = call;
+    let method = request.method().to_string();
+    // Until a request goes out, the headers it was built with stand for it.
+    let mut request_headers = redact(header_map(request.headers()), &secret_headers);
 
+    let started = std::time::Instant::now();
+    let exchange = async {
+        let (resp, sent) = match (digest, oauth2) {
+            (Some(login), _) => send_digest(client, request, &login).await?,
+            (None, Some(grant)) => send_oauth2((client, tokens), request, &grant).await?,
+            (None, None) => {
+                let sent = request.headers().clone();
+                (client.execute(request).await?, sent)
+            }
+        };
         tracing::info!("Response: {:?}", resp);
 
-        responses.push(CallResult {
-            payload: s,
-            path: url.clone(),
-            status_code: resp.status().as_u16(),
-        });
+        let status_code = resp.status().as_u16();
+        let headers = header_map(resp.headers());
+        Ok::<_, reqwest::Error>((sent, (status_code, headers, resp.text().await?)))
     }
+    .await;
+    let elapsed_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
 
-    Ok(responses)
+    let (outcome, error, (status_code, response_headers, response_body)) = match exchange {
+        Ok((sent, exchange)) => {
+            request_headers = redact(header_map(&sent), &secret_headers);
+            (Outcome::Response, None, exchange)
+        }
+        Err(e) if fail_fast => return Err(e),
+        Err(e) => {
+            let outcome = classify(&e);
+            tracing::warn!("{method} {path} failed ({outcome:?}): {e}");
+            let message = error_chain(&e);
+            (outcome, Some(message), Default::default())
+        }
+    };
+
+    Ok(CallResult {
+        method,
+        operation_id: None,
+        variant,
+        payload,
+        path,
+        query,
+        headers,
+        request_headers,
+        status_code,
+        retries: 0,
+        outcome,
+        error,
+        elapsed_ms,
+        expected,
+        auth_probe: probe,
+        undocumented_status: false,
+        auth_bypass: false,
+        response_headers,
+        response_body,
+        response_body_truncated: false,
+        body_violations: vec![],
+        response_mismatches: vec![],
+    })
 }
 
-fn retrieve_base_url(spec: &oas3::Spec) -> String {
-    spec.servers.first().map_or_else(
-        || {
-            tracing::error!("No servers found in the openapi schema");
-            std::process::exit(1);
-        },
-        |s| {
-            // When the server URL is templated, fall back to the first
-            // variable's default value (which holds the concrete URL here).
-            s.variables
-                .iter()
-                .next()
-                .map_or_else(|| s.url.clone(), |(_, var)| var.default.clone())
-        },
-    )
+/// Sends a request and, when the server answers 401 with a Digest challenge,
+/// sends it again with the answer. Both exchanges count as one call. Returns
+/// the last response with the headers of the request that got it.
+async fn send_digest(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+    (user, password): &(String, String),
+) -> Result<(reqwest::Response, reqwest::header::HeaderMap), reqwest::Error> {
+    let sent = request.headers().clone();
+    let Some(mut again) = request.try_clone() else {
+        return Ok((client.execute(request).await?, sent));
+    };
+
+    let resp = client.execute(request).await?;
+    if resp.status() != reqwest::StatusCode::UNAUTHORIZED {
+        return Ok((resp, sent));
+    }
+
+    let url = again.url();
+    let uri = url
+        .query()
+        .map_or_else(|| url.path().to_owned(), |q| format!("{}?{q}", url.path()));
+    let cnonce = format!(
+        "{:016x}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    );
+    let answer = resp
+        .headers()
+        .get_all(reqwest::header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find_map(|challenge| {
+            auth::digest_authorization(
+                challenge,
+                (user, password),
+                (again.method().as_str(), &uri),
+                &cnonce,
+            )
+        })
+        .and_then(|answer| reqwest::header::HeaderValue::from_str(&answer).ok());
+    let Some(answer) = answer else {
+        return Ok((resp, sent));
+    };
+
+    again
+        .headers_mut()
+        .insert(reqwest::header::AUTHORIZATION, answer);
+    let sent = again.headers().clone();
+    Ok((client.execute(again).await?, sent))
+}
+
+/// Sends a request with the `OAuth2` token of `grant` and, when the server
+/// rejects it with a 401, once more with a renewed one. Without a token the
+/// request goes out bare. Returns the last response with the headers of the
+/// request that got it.
+async fn send_oauth2(
+    (client, tokens): (&reqwest::Client, &auth::Tokens),
+    mut request: reqwest::Request,
+    grant: &auth::Grant,
+) -> Result<(reqwest::Response, reqwest::header::HeaderMap), reqwest::Error> {
+    let bearer = |request: &mut reqwest::Request, token: &str| {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&format!("Bearer {token}")) {
+            request
+                .headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, value);
+        }
+    };
+
+    let Some(token) = tokens.get(client, grant).await else {
+        let sent = request.headers().clone();
+        return Ok((client.execute(request).await?, sent));
+    };
+    let again = request.try_clone();
+    bearer(&mut request, &token);
+
+    let sent = request.headers().clone();
+    let resp = client.execute(request).await?;
+    let Some(mut again) = again.filter(|_| resp.status() == reqwest::StatusCode::UNAUTHORIZED)
+    else {
+        return Ok((resp, sent));
+    };
+
+    tokens.reject(grant, &token).await;
+    match tokens.get(client, grant).await {
+        Some(renewed) if renewed != token => {
+            bearer(&mut again, &renewed);
+            let sent = again.headers().clone();
+            Ok((client.execute(again).await?, sent))
+        }
+        _ => Ok((resp, sent)),
+    }
+}
+
+/// Tells which kind of transport failure an error is, from what reqwest knows
+/// and from the I/O errors underneath it.
+fn classify(error: &reqwest::Error) -> Outcome {
+    use std::io::ErrorKind;
+
+    if error.is_timeout() {
+        return Outcome::Timeout;
+    }
+
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                ErrorKind::TimedOut => return Outcome::Timeout,
+                ErrorKind::ConnectionRefused => return Outcome::Connect,
+                ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof => return Outcome::Reset,
+                _ => {}
+            }
+        }
+        source = e.source();
+    }
+
+    // The TLS and HTTP layers don't expose typed errors, only their messages.
+    let message = error_chain(error).to_lowercase();
+    if ["certificate", "tls", "ssl", "handshake"]
+        .iter()
+        .any(|hint| message.contains(hint))
+    {
+        Outcome::Tls
+    } else if error.is_connect() {
+        Outcome::Connect
+    } else if message.contains("connection closed") || message.contains("reset") {
+        Outcome::Reset
+    } else {
+        Outcome::Transport
+    }
+}
+
+/// Joins an error with its sources, since reqwest's own message is often as
+/// vague as "error sending request".
+fn error_chain(error: &reqwest::Error) -> String {
+    let mut parts = vec![error.to_string()];
+    let mut source = std::error::Error::source(error);
+    while let Some(e) = source {
+        parts.push(e.to_string());
+        source = e.source();
+    }
+    parts.join(": ")
+}
+
+/// Names the input combination of a call from its position among the body,
+/// query and header variants. Dimensions with a single variant are left out.
+fn variant_label(dimensions: &[(&str, usize, usize)]) -> String {
+    let parts: Vec<String> = dimensions
+        .iter()
+        .filter(|(_, _, total)| *total > 1)
+        .map(|(name, index, total)| format!("{name} {}/{total}", index + 1))
+        .collect();
+
+    if parts.is_empty() {
+        "single".to_owned()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Hides the values of headers carrying credentials, so results can be shared:
+/// the `secret` ones and any whose name hints at a secret.
+fn redact(
+    mut headers: std::collections::BTreeMap<String, String>,
+    secret: &[String],
+) -> std::collections::BTreeMap<String, String> {
+    const SECRET_HINTS: [&str; 7] = [
+        "auth", "cookie", "token", "secret", "key", "password", "session",
+    ];
+
+    for (name, value) in &mut headers {
+        if secret.contains(name) || SECRET_HINTS.iter().any(|hint| name.contains(hint)) {
+            "<redacted>".clone_into(value);
+        }
+    }
+    headers
+}
+
+/// Cuts the recorded response body down to [`MAX_RECORDED_BODY`] bytes, on a
+/// character boundary.
+fn cap_body(result: &mut CallResult) {
+    if result.response_body.len() <= MAX_RECORDED_BODY {
+        return;
+    }
+
+    let mut end = MAX_RECORDED_BODY;
+    while !result.response_body.is_char_boundary(end) {
+        end -= 1;
+    }
+    result.response_body.truncate(end);
+    result.response_body_truncated = true;
+}
+
+fn header_map(headers: &reqwest::header::HeaderMap) -> std::collections::BTreeMap<String, String> {
+    let mut map = std::collections::BTreeMap::<String, String>::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        map.entry(name.as_str().to_owned())
+            .and_modify(|v| {
+                v.push_str(", ");
+                v.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    map
+}
+
+fn warn_capped(method: &reqwest::Method, max_requests: usize) {
+    tracing::warn!(
+        "{method}: stopped at {max_requests} requests, the rest left out by the per-operation cap"
+    );
+}
+
+fn retrieve_base_url(
+    spec: &oas3::Spec,
+    (selector, vars): (Option<&str>, &std::collections::BTreeMap<String, String>),
+) -> Result<String, Error> {
+    if spec.servers.is_empty() {
+        return Err(Error::NoServers);
+    }
+
+    servers::pick(&spec.servers, selector)
+        .map(|server| servers::expand(server, vars))
+        .map_err(|e| Error::Spec(format!("cannot pick the server: {e}")))
+}
+
+/// A map of owned strings out of literal pairs, like the headers of a
+/// response or the secrets at hand.
+#[cfg(test)]
+fn string_map(pairs: &[(&str, &str)]) -> std::collections::BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+        .collect()
 }
 
 #[cfg(test)]
@@ -343,14 +1401,16 @@ mod tests {
             let s = std::include_str!("./testdata/single_server.yml");
             let spec = parse_openapi(s).unwrap();
 
-            let base = retrieve_base_url(&spec);
+            let base =
+                retrieve_base_url(&spec, (None, &std::collections::BTreeMap::new())).unwrap();
             assert_eq!(base, "http://127.0.0.1:8000");
         }
         {
             // server from env
             let s = std::include_str!("./testdata/server_from_env.yml");
             let spec = parse_openapi(s).unwrap();
-            let base = retrieve_base_url(&spec);
+            let base =
+                retrieve_base_url(&spec, (None, &std::collections::BTreeMap::new())).unwrap();
             assert_eq!(base, "http://localhost:8000"); // pick the default one
         }
     }
@@ -359,17 +1419,15 @@ mod tests {
     #[test]
     fn fake_test() {
         tracing_subscriber::fmt::init();
-        assert!(true);
     }
 
     #[test]
     fn find_jwt_token_in_components() {
         let s = std::include_str!("./testdata/get_more_info_with_jwt.yml");
         let spec = parse_openapi(s).unwrap();
-        let jwt = get_jwt_token(&spec);
+        let jwt = auth::http_schemes(&spec, "bearer");
 
-        assert!(jwt.is_some());
-        assert_eq!(jwt.unwrap(), "bearerAuth");
+        assert_eq!(jwt, ["bearerAuth"]);
     }
 
     #[test]
@@ -377,9 +1435,9 @@ mod tests {
         // Test with a spec that has no security schemes
         let s = std::include_str!("./testdata/get_info.yml");
         let spec = parse_openapi(s).unwrap();
-        let jwt = get_jwt_token(&spec);
+        let jwt = auth::http_schemes(&spec, "bearer");
 
-        assert!(jwt.is_none());
+        assert!(jwt.is_empty());
     }
 
     #[test]
@@ -387,18 +1445,102 @@ mod tests {
         // When there are multiple servers, it should pick the first one
         let s = std::include_str!("./testdata/multiple_servers.yml");
         let spec = parse_openapi(s).unwrap();
-        let base = retrieve_base_url(&spec);
+        let base = retrieve_base_url(&spec, (None, &std::collections::BTreeMap::new())).unwrap();
 
         assert_eq!(base, "http://first.example.com");
     }
 
+    #[test]
+    fn default_client_options_build() {
+        assert!(ClientOptions::default().build().is_ok());
+    }
+
+    #[test]
+    fn invalid_proxy_fails_the_client() {
+        let options = ClientOptions {
+            proxy: Some("http://[::1".to_owned()),
+            ..ClientOptions::default()
+        };
+        assert!(options.build().is_err());
+    }
+
+    #[test]
+    fn variant_labels_skip_single_dimensions() {
+        assert_eq!(
+            variant_label(&[("body", 2, 8), ("query", 0, 1), ("headers", 1, 2)]),
+            "body 3/8, headers 2/2"
+        );
+        assert_eq!(variant_label(&[("query", 0, 1)]), "single");
+    }
+
+    #[test]
+    fn credentials_are_redacted() {
+        let headers = redact(
+            [
+                ("authorization", "Bearer abc"),
+                ("x-api-key", "k"),
+                ("cookie", "session=1"),
+                ("content-type", "application/json"),
+                ("x-tenant-id", "acme"),
+                ("x-org-access", "s3cr3t"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+            &["x-org-access".to_owned()],
+        );
+
+        assert_eq!(headers["authorization"], "<redacted>");
+        assert_eq!(headers["x-api-key"], "<redacted>");
+        assert_eq!(headers["cookie"], "<redacted>");
+        assert_eq!(headers["content-type"], "application/json");
+        assert_eq!(headers["x-tenant-id"], "acme");
+        assert_eq!(headers["x-org-access"], "<redacted>");
+    }
+
+    #[test]
+    fn long_bodies_are_cut_on_a_char_boundary() {
+        let mut r = CallResult {
+            method: "GET".to_owned(),
+            operation_id: None,
+            variant: "single".to_owned(),
+            payload: String::new(),
+            path: String::new(),
+            query: String::new(),
+            headers: std::collections::BTreeMap::new(),
+            request_headers: std::collections::BTreeMap::new(),
+            status_code: 200,
+            retries: 0,
+            outcome: Outcome::Response,
+            error: None,
+            elapsed_ms: 0,
+            expected: Expectation::Valid,
+            auth_probe: None,
+            undocumented_status: false,
+            auth_bypass: false,
+            response_headers: std::collections::BTreeMap::new(),
+            // 3 bytes per char, so the cap falls inside one
+            response_body: "€".repeat(MAX_RECORDED_BODY),
+            response_body_truncated: false,
+            body_violations: vec![],
+            response_mismatches: vec![],
+        };
+
+        cap_body(&mut r);
+        assert!(r.response_body_truncated);
+        assert!(r.response_body.len() <= MAX_RECORDED_BODY);
+        assert!(r.response_body.chars().all(|c| c == '€'));
+    }
+
     #[test]
     fn jwt_token_ignored_for_non_bearer_http_scheme() {
-        // A basic-auth scheme is HTTP but not bearer, so no JWT name is found.
+        // A basic-auth scheme is HTTP but not bearer, so it takes the basic
+        // login rather than the JWT.
         let s = std::include_str!("./testdata/get_info_basic_auth.yml");
         let spec = parse_openapi(s).unwrap();
 
-        assert!(get_jwt_token(&spec).is_none());
+        assert!(auth::http_schemes(&spec, "bearer").is_empty());
+        assert_eq!(auth::http_schemes(&spec, "basic"), ["basicAuth"]);
     }
 
     #[test]
@@ -407,6 +1549,6 @@ mod tests {
         let s = std::include_str!("./testdata/get_more_info_with_jwt.yml");
         let spec = parse_openapi(s).unwrap();
 
-        assert_eq!(get_jwt_token(&spec).unwrap(), "bearerAuth");
+        assert_eq!(auth::http_schemes(&spec, "BEARER"), ["bearerAuth"]);
     }
 }
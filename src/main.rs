@@ -10,14 +10,20 @@ struct Args {
     #[clap(long, short)]
     base_url: Option<String>,
 
+    /// Server of the spec to use instead of the first one, by index in
+    /// `servers` or by description
+    #[clap(long, conflicts_with = "base_url")]
+    server: Option<String>,
+
+    /// Value of a server URL variable, as `name=value`, instead of its
+    /// default. Repeat for each variable
+    #[clap(long = "server-var", value_parser = key_value, conflicts_with = "base_url")]
+    server_vars: Vec<(String, String)>,
+
     /// Skip deprecated endpoints
     #[clap(long)]
     skip_deprecated: bool,
 
-    /// Token JWT to use in request headers
-    #[clap(long)]
-    jwt: Option<String>,
-
     /// Print the raw per-request results as JSON before the summary
     #[clap(long)]
     json: bool,
@@ -29,6 +35,189 @@ struct Args {
     /// Apply the delay only after every N requests instead of after each one
     #[clap(long = "delay-every", default_value_t = 1)]
     delay_every: usize,
+
+    /// Send at most this many requests per second, instead of --delay
+    #[clap(long, conflicts_with_all = ["delay", "delay_every"])]
+    rate: Option<f64>,
+
+    /// Retry a call answered with 429 or 503 up to N times, waiting as long
+    /// as Retry-After or the `RateLimit` headers ask
+    #[clap(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Which body payloads to send
+    #[clap(long, value_enum, default_value_t = Mode::Exhaustive)]
+    mode: Mode,
+
+    /// Only send enough combinations to cover every interaction of N
+    /// properties (2 = pairwise) instead of all of them; lowered with a
+    /// warning when the interactions to cover would be too many
+    #[clap(long, value_parser = clap::value_parser!(u16).range(1..))]
+    strength: Option<u16>,
+
+    /// Stop drilling an operation after N requests, auth probes included
+    #[clap(long = "max-requests")]
+    max_requests: Option<usize>,
+
+    /// Abort the run at the first transport failure (timeout, refused
+    /// connection, TLS, reset) instead of moving on
+    #[clap(long = "fail-fast")]
+    fail_fast: bool,
+
+    /// How many requests to keep in flight at once
+    #[clap(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: u16,
+
+    #[command(flatten)]
+    auth: AuthArgs,
+
+    #[command(flatten)]
+    client: ClientArgs,
+
+    #[command(flatten)]
+    oauth2: OAuth2Args,
+}
+
+/// Credentials, listed under their own heading in `--help`.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Authentication")]
+struct AuthArgs {
+    /// Token JWT to use in request headers
+    #[clap(long)]
+    jwt: Option<String>,
+
+    /// Login for HTTP basic security schemes, as `user:password`
+    #[clap(long)]
+    basic: Option<String>,
+
+    /// Secret for a security scheme of the spec, as `scheme=secret`; sent
+    /// where the scheme says (header, query or cookie). Basic and Digest
+    /// schemes take `user:password`. Repeat for each scheme
+    #[clap(long = "auth", value_parser = key_value)]
+    auth: Vec<(String, String)>,
+
+    /// Also call every secured endpoint without credentials, with invalid
+    /// ones and with a credential of the wrong scheme, reporting the calls
+    /// not refused with 401 or 403
+    #[clap(long = "auth-check")]
+    auth_check: bool,
+}
+
+/// `OAuth2` client settings, listed under their own heading in `--help`.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "OAuth2")]
+struct OAuth2Args {
+    /// Client id to get tokens for the `OAuth2` schemes of the spec, from
+    /// their clientCredentials flow (or password flow, with --oauth2-login)
+    #[clap(long = "oauth2-client-id")]
+    client_id: Option<String>,
+
+    /// Client secret, sent to the token endpoint with HTTP Basic
+    #[clap(long = "oauth2-client-secret", requires = "client_id")]
+    client_secret: Option<String>,
+
+    /// Scope to ask for, instead of the ones each operation requires. Repeat
+    /// for each scope
+    #[clap(long = "oauth2-scope", requires = "client_id")]
+    scopes: Vec<String>,
+
+    /// Token endpoint to use instead of the flows' tokenUrl
+    #[clap(long = "oauth2-token-url", requires = "client_id")]
+    token_url: Option<String>,
+
+    /// Resource owner login for the password flow, as `user:password`
+    #[clap(long = "oauth2-login", value_parser = login, requires = "client_id")]
+    login: Option<(String, String)>,
+}
+
+impl OAuth2Args {
+    fn options(self) -> Option<fiuto::OAuth2> {
+        Some(fiuto::OAuth2 {
+            client_id: self.client_id?,
+            client_secret: self.client_secret,
+            scopes: self.scopes,
+            token_url: self.token_url,
+            login: self.login,
+        })
+    }
+}
+
+/// Connection settings, listed under their own heading in `--help`.
+#[derive(clap::Args, Debug)]
+#[command(next_help_heading = "Connection")]
+struct ClientArgs {
+    /// Milliseconds to wait for a connection to open, 0 to wait forever
+    #[clap(long = "connect-timeout", default_value_t = 10_000)]
+    connect_timeout: u64,
+
+    /// Milliseconds a whole request may take before giving up, 0 to wait
+    /// forever
+    #[clap(long, default_value_t = 30_000)]
+    timeout: u64,
+
+    /// Open a new connection for every request instead of reusing them
+    #[clap(long = "no-keep-alive")]
+    no_keep_alive: bool,
+
+    /// Speak HTTP/2 from the start, also over plain http
+    #[clap(long)]
+    http2: bool,
+
+    /// Send every request through this proxy
+    #[clap(long)]
+    proxy: Option<String>,
+
+    /// PEM file with a CA certificate to trust, for servers signed by a
+    /// private CA
+    #[clap(long = "ca-cert")]
+    ca_cert: Option<std::path::PathBuf>,
+
+    /// Accept invalid and self-signed TLS certificates
+    #[clap(long)]
+    insecure: bool,
+}
+
+impl ClientArgs {
+    fn options(self) -> fiuto::ClientOptions {
+        let ca_cert = match self.ca_cert.as_ref().map(std::fs::read).transpose() {
+            Ok(pem) => pem,
+            Err(e) => {
+                tracing::error!("Error reading the CA certificate: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+
+        fiuto::ClientOptions {
+            connect_timeout: millis(self.connect_timeout),
+            timeout: millis(self.timeout),
+            keep_alive: !self.no_keep_alive,
+            http2: self.http2,
+            proxy: self.proxy,
+            ca_cert,
+            insecure: self.insecure,
+        }
+    }
+}
+
+/// Command line spelling of [`fiuto::Mode`].
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Mode {
+    /// Every combination of properties, valid or not
+    Exhaustive,
+    /// Only payloads with every required property
+    Valid,
+    /// The complete payload missing one required property at a time
+    MissingRequired,
+}
+
+impl From<Mode> for fiuto::Mode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Exhaustive => Self::Exhaustive,
+            Mode::Valid => Self::Valid,
+            Mode::MissingRequired => Self::MissingRequired,
+        }
+    }
 }
 
 #[tokio::main]
@@ -56,32 +245,93 @@ async fn main() {
     let throttle = fiuto::Throttle {
         delay: std::time::Duration::from_millis(args.delay),
         every: args.delay_every.max(1),
+        rate: args.rate,
+        retries: args.retries,
     };
 
-    let mut driller = fiuto::Driller::new(openapi_schema).throttle(throttle);
+    let mut driller = fiuto::Driller::new(openapi_schema)
+        .throttle(throttle)
+        .client(args.client.options())
+        .mode(args.mode.into())
+        .fail_fast(args.fail_fast)
+        .auth_check(args.auth.auth_check)
+        .concurrency(args.concurrency.into());
+    if let Some(strength) = args.strength {
+        driller = driller.coverage(fiuto::Coverage::TWay(strength.into()));
+    }
+    if let Some(max) = args.max_requests {
+        driller = driller.max_requests_per_operation(max);
+    }
     if let Some(base_url) = args.base_url {
         driller = driller.base_url(base_url);
     }
-    if let Some(jwt) = args.jwt {
+    if let Some(server) = args.server {
+        driller = driller.server(server);
+    }
+    for (name, value) in args.server_vars {
+        driller = driller.server_var(name, value);
+    }
+    if let Some(jwt) = args.auth.jwt {
         driller = driller.jwt(jwt);
     }
+    if let Some(basic) = args.auth.basic {
+        let Some((user, password)) = basic.split_once(':') else {
+            tracing::error!("--basic expects user:password");
+            std::process::exit(1);
+        };
+        driller = driller.basic(user, password);
+    }
+    for (scheme, secret) in args.auth.auth {
+        driller = driller.auth(scheme, secret);
+    }
+    if let Some(oauth2) = args.oauth2.options() {
+        driller = driller.oauth2(oauth2);
+    }
 
-    let all_results = match driller.run().await {
+    let report = match driller.run().await {
         Ok(v) => v,
         Err(e) => {
-            tracing::error!("Error executing operations: {:?}", e);
+            tracing::error!("Error executing operations: {e}");
             std::process::exit(1);
         }
     };
 
     if args.json {
-        for r in &all_results {
+        for r in &report.results {
             let string_results = serde_json::to_string_pretty(&r).unwrap(); // FIXME: handle the error
             println!("{string_results}");
         }
     }
 
-    print_summary(&all_results);
+    print_summary(&report.results);
+    print_findings(
+        "operation(s) skipped, their requests cannot be built from the spec",
+        &report
+            .skipped
+            .iter()
+            .map(|(operation, e)| format!("{operation}  {}", truncate(&e.to_string(), 80)))
+            .collect::<Vec<_>>(),
+    );
+}
+
+/// Splits a `name=value` argument.
+fn key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(format!("expected name=value, got {s:?}")),
+    }
+}
+
+/// Splits a `user:password` argument.
+fn login(s: &str) -> Result<(String, String), String> {
+    s.split_once(':')
+        .map(|(user, password)| (user.to_owned(), password.to_owned()))
+        .ok_or_else(|| "expected user:password".to_owned())
+}
+
+/// Turns a timeout flag into a duration, `0` meaning no timeout at all.
+fn millis(ms: u64) -> Option<std::time::Duration> {
+    (ms > 0).then(|| std::time::Duration::from_millis(ms))
 }
 
 /// Renders a fixed-width bar scaled so that `max` fills `width` cells.
@@ -113,11 +363,30 @@ fn print_summary(all_results: &[Vec<fiuto::CallResult>]) {
     let mut codes: std::collections::BTreeMap<u16, u32> = std::collections::BTreeMap::new();
     let mut classes = [0u32; 5]; // index 0 -> 1xx, ... index 4 -> 5xx
     let mut total = 0u32;
+    let failures: Vec<String> = all_results
+        .iter()
+        .flatten()
+        .filter(|cr| cr.outcome != fiuto::Outcome::Response)
+        .map(|cr| {
+            let kind = format!("{:?}", cr.outcome).to_lowercase();
+            let error = cr.error.as_deref().unwrap_or_default();
+            format!(
+                "{kind:<9} {} {}  {}",
+                cr.method,
+                cr.path,
+                truncate(error, 80)
+            )
+        })
+        .collect();
 
     for r in all_results {
         for cr in r {
-            *codes.entry(cr.status_code).or_default() += 1;
             total += 1;
+            // Failed calls have no status, they are listed on their own.
+            if cr.outcome != fiuto::Outcome::Response {
+                continue;
+            }
+            *codes.entry(cr.status_code).or_default() += 1;
 
             let class = (cr.status_code / 100) as usize;
             if (1..=5).contains(&class) {
@@ -128,7 +397,10 @@ fn print_summary(all_results: &[Vec<fiuto::CallResult>]) {
 
     println!();
     println!("════════════════════ fiuto summary ════════════════════");
-    println!("requests: {total}    endpoints: {endpoints}");
+    println!(
+        "requests: {total}    endpoints: {endpoints}    failed: {}",
+        failures.len()
+    );
 
     let class_labels = [
         "1xx info",
@@ -161,37 +433,144 @@ fn print_summary(all_results: &[Vec<fiuto::CallResult>]) {
         println!("  {code:>3} {count:>4}  {}", bar(*count, max_code, 30));
     }
 
+    print_findings("transport failure(s), no response received", &failures);
+
+    // Bad credentials getting anything but a refusal is a security hole, or
+    // at best a server checking the input before the caller.
+    print_findings(
+        "secured endpoint(s) not refusing bad credentials with 401/403",
+        &all_results
+            .iter()
+            .flatten()
+            .filter(|cr| cr.auth_bypass)
+            .map(|cr| {
+                let probe = cr.auth_probe.map(|p| format!("{p:?}")).unwrap_or_default();
+                format!("{} {} {}  {probe}", cr.status_code, cr.method, cr.path)
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    print_contract_findings(all_results);
+
     // A fuzzer driving random payloads should never make the server crash,
     // so surface every 5xx as a likely bug with the payload that caused it.
-    let server_errors: Vec<&fiuto::CallResult> = all_results
+    print_findings(
+        "server error(s) (5xx), possible bugs",
+        &all_results
+            .iter()
+            .flatten()
+            .filter(|cr| cr.status_code >= 500)
+            .map(|cr| {
+                let payload = if cr.payload.is_empty() {
+                    "<empty>".to_owned()
+                } else {
+                    truncate(&cr.payload, 80)
+                };
+                format!("{} {}  {}", cr.status_code, cr.path, payload)
+            })
+            .collect::<Vec<_>>(),
+    );
+}
+
+/// Lists where the responses drift from what the spec documents.
+fn print_contract_findings(all_results: &[Vec<fiuto::CallResult>]) {
+    // A status the spec does not list is contract drift, whatever its class.
+    let undocumented: std::collections::BTreeSet<(u16, &str)> = all_results
         .iter()
         .flatten()
-        .filter(|cr| cr.status_code >= 500)
+        .filter(|cr| cr.undocumented_status)
+        .map(|cr| (cr.status_code, cr.path.as_str()))
         .collect();
+    print_findings(
+        "undocumented status code(s), not listed in the operation responses",
+        &undocumented
+            .iter()
+            .map(|(code, path)| format!("{code} {path}"))
+            .collect::<Vec<_>>(),
+    );
+
+    // The response body drifting from its documented schema.
+    print_findings(
+        "response(s) not matching the documented schema",
+        &all_results
+            .iter()
+            .flatten()
+            .filter_map(|cr| {
+                let v = cr.body_violations.first()?;
+                let pointer = if v.pointer.is_empty() {
+                    "/"
+                } else {
+                    &v.pointer
+                };
+                Some(format!(
+                    "{} {}  {pointer}: {}",
+                    cr.status_code,
+                    cr.path,
+                    truncate(&v.message, 80)
+                ))
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    // Response metadata drifting from the spec, next to the status checks.
+    print_findings(
+        "response(s) with undocumented headers or content type",
+        &all_results
+            .iter()
+            .flatten()
+            .flat_map(|cr| {
+                cr.response_mismatches
+                    .iter()
+                    .map(move |m| format!("{} {}  {}", cr.status_code, cr.path, truncate(m, 80)))
+            })
+            .collect::<Vec<_>>(),
+    );
+}
 
-    if !server_errors.is_empty() {
-        println!();
-        println!(
-            "⚠ {} server error(s) (5xx), possible bugs",
-            server_errors.len()
-        );
-        for cr in server_errors.iter().take(20) {
-            let payload = if cr.payload.is_empty() {
-                "<empty>".to_owned()
-            } else {
-                truncate(&cr.payload, 80)
-            };
-            println!("  {} {}  {}", cr.status_code, cr.path, payload);
-        }
-        if server_errors.len() > 20 {
-            println!("  ... {} more", server_errors.len() - 20);
-        }
+/// Prints a warning section listing the first 20 findings, if there is any.
+fn print_findings(title: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("⚠ {} {title}", lines.len());
+    for line in lines.iter().take(20) {
+        println!("  {line}");
+    }
+    if lines.len() > 20 {
+        println!("  ... {} more", lines.len() - 20);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{bar, truncate};
+    use super::{bar, key_value, login, millis, truncate};
+
+    #[test]
+    fn key_value_splits_on_the_first_equal() {
+        assert_eq!(
+            key_value("api_key=a=b"),
+            Ok(("api_key".to_owned(), "a=b".to_owned()))
+        );
+        assert!(key_value("api_key").is_err());
+        assert!(key_value("=secret").is_err());
+    }
+
+    #[test]
+    fn login_splits_on_the_first_colon() {
+        assert_eq!(
+            login("alice:s3:cret"),
+            Ok(("alice".to_owned(), "s3:cret".to_owned()))
+        );
+        assert!(login("alice").is_err());
+    }
+
+    #[test]
+    fn zero_timeout_means_none() {
+        assert_eq!(millis(0), None);
+        assert_eq!(millis(250), Some(std::time::Duration::from_millis(250)));
+    }
 
     #[test]
     fn bar_is_empty_when_max_is_zero() {
@@ -0,0 +1,595 @@
+use oas3::Spec;
+use oas3::spec::{ObjectOrReference, Parameter, ParameterIn, ParameterStyle};
+
+/// Characters left as-is when a value is placed in a URL: the RFC 3986
+/// unreserved set. Everything else is percent-encoded so a value can never
+/// break out of its slot.
+pub const UNRESERVED: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Header parameters with these names are ignored, as the spec mandates:
+/// they are driven by the request itself, not by the parameter list.
+const RESERVED_HEADERS: [&str; 3] = ["accept", "content-type", "authorization"];
+
+/// A set of `(name, value)` headers sent together on one request.
+pub type HeaderSet = Vec<(String, String)>;
+
+/// Picks the value to send for a parameter. The parameter's own `example`
+/// wins, then the first of its `examples`, then the schema's `example`, and
+/// finally a value synthesized from the schema.
+pub fn example_value(param: &Parameter, spec: &Spec) -> Option<serde_json::Value> {
+    example_values(param, spec).into_iter().next()
+}
+
+/// Like [`example_value`] but returns every entry of `examples` instead of
+/// only the first one.
+fn example_values(param: &Parameter, spec: &Spec) -> Vec<serde_json::Value> {
+    if let Some(v) = &param.example {
+        return vec![v.clone()];
+    }
+
+    let from_examples: Vec<serde_json::Value> = param
+        .examples
+        .values()
+        .filter_map(|e| match e {
+            ObjectOrReference::Object(e) => e.value.clone(),
+            ObjectOrReference::Ref { .. } => e.resolve(spec).ok()?.value,
+        })
+        .collect();
+    if !from_examples.is_empty() {
+        return from_examples;
+    }
+
+    let Some(schema) = param
+        .schema
+        .as_ref()
+        .and_then(|s| crate::collector::resolve_object_schema(s, spec).ok())
+    else {
+        return vec![];
+    };
+
+    schema
+        .example
+        .clone()
+        .or_else(|| crate::synthesizer::synthesize(&schema, spec))
+        .into_iter()
+        .collect()
+}
+
+/// Replaces every `{name}` in a path template with the value of the matching
+/// `in: path` parameter, serialized according to its `style`/`explode` and
+/// percent-encoded.
+pub fn expand_path(template: &str, params: &[Parameter], spec: &Spec) -> Result<String, String> {
+    let mut path = template.to_owned();
+
+    for p in params.iter().filter(|p| p.location == ParameterIn::Path) {
+        let placeholder = format!("{{{}}}", p.name);
+        if !path.contains(&placeholder) {
+            continue;
+        }
+
+        let value = example_value(p, spec)
+            .ok_or_else(|| format!("no value for path parameter: {}", p.name))?;
+        path = path.replace(&placeholder, &serialize_path(p, &value));
+    }
+
+    // Substituted values are percent-encoded, so any brace left over is a
+    // template expression without a declared parameter.
+    if path.contains('{') {
+        return Err(format!("undeclared path parameter in {template}"));
+    }
+
+    Ok(path)
+}
+
+/// Serializes a path parameter value following the `simple` (default),
+/// `label` and `matrix` styles.
+fn serialize_path(param: &Parameter, value: &serde_json::Value) -> String {
+    let explode = param.explode.unwrap_or(false);
+    let name = encode(&param.name);
+
+    // Each entry is an optional key (objects only) and its encoded value.
+    let items: Vec<(Option<String>, String)> = match value {
+        serde_json::Value::Array(a) => a.iter().map(|v| (None, encode(&to_text(v)))).collect(),
+        serde_json::Value::Object(o) => o
+            .iter()
+            .map(|(k, v)| (Some(encode(k)), encode(&to_text(v))))
+            .collect(),
+        v => vec![(None, encode(&to_text(v)))],
+    };
+
+    // Without explode, object keys and values are flattened into one list.
+    let flat = || {
+        items
+            .iter()
+            .flat_map(|(k, v)| k.iter().chain(std::iter::once(v)))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    match param.style {
+        Some(ParameterStyle::Matrix) if explode => items
+            .iter()
+            .map(|(k, v)| format!(";{}={v}", k.as_ref().unwrap_or(&name)))
+            .collect::<Vec<_>>()
+            .concat(),
+        Some(ParameterStyle::Matrix) => format!(";{name}={}", flat()),
+        Some(ParameterStyle::Label) if explode => items
+            .iter()
+            .map(|(k, v)| {
+                k.as_ref()
+                    .map_or_else(|| format!(".{v}"), |k| format!(".{k}={v}"))
+            })
+            .collect::<Vec<_>>()
+            .concat(),
+        Some(ParameterStyle::Label) => format!(".{}", flat()),
+        _ if explode => items
+            .iter()
+            .map(|(k, v)| k.as_ref().map_or_else(|| v.clone(), |k| format!("{k}={v}")))
+            .collect::<Vec<_>>()
+            .join(","),
+        _ => flat(),
+    }
+}
+
+/// Builds the query strings to drill for an operation, lazily so callers can
+/// stop at their cap. Required parameters are always present, while the
+/// optional ones go through the same power set the shuffler applies to body
+/// properties, plus the required-only variant. Parameters keep their
+/// declaration order in the rendered string.
+pub fn query_combinations(params: &[Parameter], spec: &Spec) -> impl Iterator<Item = String> {
+    let mut required = vec![];
+    let mut optional = vec![];
+
+    for p in params.iter().filter(|p| p.location == ParameterIn::Query) {
+        let Some(v) = example_value(p, spec) else {
+            tracing::warn!("No example found for query parameter: {}, skipping", p.name);
+            continue;
+        };
+
+        if p.required.unwrap_or(false) {
+            required.push((p, v));
+        } else {
+            optional.push((p, v));
+        }
+    }
+
+    let root = crate::digger::Node::new("root", serde_json::Value::Null);
+    for (p, v) in &optional {
+        root.borrow_mut()
+            .children
+            .push(crate::digger::Node::new(&p.name, v.clone()));
+    }
+
+    // the required-only variant, which is the empty query when nothing is required
+    let subsets = crate::shuffler::combinations(&root)
+        .chain(std::iter::once(std::collections::HashMap::new()));
+
+    subsets.map(move |subset| {
+        required
+            .iter()
+            .chain(
+                optional
+                    .iter()
+                    .filter(|(p, _)| subset.contains_key(&p.name)),
+            )
+            .map(|(p, v)| serialize_query(p, v))
+            .collect::<Vec<_>>()
+            .join("&")
+    })
+}
+
+/// Serializes a query parameter into its `name=value` pairs following the
+/// `form` (default), `spaceDelimited`, `pipeDelimited` and `deepObject`
+/// styles. `form` explodes by default, the other styles do not.
+fn serialize_query(param: &Parameter, value: &serde_json::Value) -> String {
+    let style = param.style.unwrap_or(ParameterStyle::Form);
+    let explode = param.explode.unwrap_or(style == ParameterStyle::Form);
+    let name = encode(&param.name);
+
+    let items: Vec<(Option<String>, String)> = match value {
+        serde_json::Value::Array(a) => a.iter().map(|v| (None, encode(&to_text(v)))).collect(),
+        serde_json::Value::Object(o) => o
+            .iter()
+            .map(|(k, v)| (Some(encode(k)), encode(&to_text(v))))
+            .collect(),
+        v => return format!("{name}={}", encode(&to_text(v))),
+    };
+
+    let delimiter = match style {
+        ParameterStyle::SpaceDelimited => "%20",
+        ParameterStyle::PipeDelimited => "|",
+        _ => ",",
+    };
+
+    match style {
+        ParameterStyle::DeepObject => items
+            .iter()
+            .map(|(k, v)| format!("{name}[{}]={v}", k.as_deref().unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join("&"),
+        // An exploded array repeats the name, an exploded object uses its keys.
+        _ if explode => items
+            .iter()
+            .map(|(k, v)| format!("{}={v}", k.as_ref().unwrap_or(&name)))
+            .collect::<Vec<_>>()
+            .join("&"),
+        _ => {
+            let flat = items
+                .iter()
+                .flat_map(|(k, v)| k.iter().chain(std::iter::once(v)))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(delimiter);
+            format!("{name}={flat}")
+        }
+    }
+}
+
+/// Builds every set of headers to drill for an operation out of its `in:
+/// header` and `in: cookie` parameters. Each parameter is sent with each of
+/// its examples and, when optional, also left out; the sets are the cartesian
+/// product of those choices, walked lazily with the last parameter changing
+/// first. Cookies are folded into a single `Cookie` header.
+pub fn header_combinations(
+    params: &[Parameter],
+    spec: &Spec,
+) -> impl Iterator<Item = HeaderSet> + use<> {
+    let mut choices: Vec<(String, bool, Vec<Option<String>>)> = vec![];
+
+    for p in params {
+        match p.location {
+            ParameterIn::Header if !RESERVED_HEADERS.contains(&p.name.to_lowercase().as_str()) => {}
+            ParameterIn::Cookie => {}
+            _ => continue,
+        }
+
+        let explode = p.explode.unwrap_or(false);
+        let mut options: Vec<Option<String>> = example_values(p, spec)
+            .iter()
+            .map(|v| Some(serialize_simple(v, explode)))
+            .collect();
+        if options.is_empty() {
+            tracing::warn!(
+                "No example found for {:?} parameter: {}, skipping",
+                p.location,
+                p.name
+            );
+            continue;
+        }
+        // absent comes last, so the first set carries every parameter
+        if !p.required.unwrap_or(false) {
+            options.push(None);
+        }
+
+        choices.push((p.name.clone(), p.location == ParameterIn::Cookie, options));
+    }
+
+    let sizes: Vec<usize> = choices.iter().map(|(_, _, o)| o.len()).collect();
+    let picks = std::iter::successors(Some(vec![0; sizes.len()]), move |picks| {
+        let mut next = picks.clone();
+        for i in (0..next.len()).rev() {
+            next[i] += 1;
+            if next[i] < sizes[i] {
+                return Some(next);
+            }
+            next[i] = 0;
+        }
+        None
+    });
+
+    picks.map(move |picks| {
+        let mut headers: HeaderSet = vec![];
+        let mut cookies = vec![];
+        for ((name, cookie, options), pick) in choices.iter().zip(picks) {
+            let Some(value) = &options[pick] else {
+                continue;
+            };
+            if *cookie {
+                cookies.push(format!("{name}={value}"));
+            } else {
+                headers.push((name.clone(), value.clone()));
+            }
+        }
+        if !cookies.is_empty() {
+            headers.push(("Cookie".to_owned(), cookies.join("; ")));
+        }
+        headers
+    })
+}
+
+/// Serializes a header or cookie value following the `simple` style, without
+/// any percent-encoding.
+fn serialize_simple(value: &serde_json::Value, explode: bool) -> String {
+    match value {
+        serde_json::Value::Array(a) => a.iter().map(to_text).collect::<Vec<_>>().join(","),
+        serde_json::Value::Object(o) if explode => o
+            .iter()
+            .map(|(k, v)| format!("{k}={}", to_text(v)))
+            .collect::<Vec<_>>()
+            .join(","),
+        serde_json::Value::Object(o) => o
+            .iter()
+            .flat_map(|(k, v)| [k.clone(), to_text(v)])
+            .collect::<Vec<_>>()
+            .join(","),
+        v => to_text(v),
+    }
+}
+
+/// Renders a JSON value as plain text: strings lose their quotes, anything
+/// else keeps its JSON form.
+fn to_text(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+fn encode(s: &str) -> String {
+    percent_encoding::utf8_percent_encode(s, UNRESERVED).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_openapi;
+
+    fn param(yaml: &str) -> Parameter {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    fn empty_spec() -> Spec {
+        parse_openapi(std::include_str!("./testdata/single_server.yml")).unwrap()
+    }
+
+    #[test]
+    fn expand_simple_primitive() {
+        let spec = empty_spec();
+        let p = param("name: id\nin: path\nrequired: true\nexample: 42");
+
+        let path = expand_path("/users/{id}", &[p], &spec).unwrap();
+        assert_eq!(path, "/users/42");
+    }
+
+    #[test]
+    fn expand_percent_encodes_reserved_characters() {
+        let spec = empty_spec();
+        let p = param("name: name\nin: path\nrequired: true\nexample: \"a b/c?\"");
+
+        let path = expand_path("/files/{name}", &[p], &spec).unwrap();
+        assert_eq!(path, "/files/a%20b%2Fc%3F");
+    }
+
+    #[test]
+    fn expand_uses_schema_example_and_default() {
+        let spec = empty_spec();
+        let first = param(
+            "name: a\nin: path\nrequired: true\nschema:\n  type: string\n  example: from-schema",
+        );
+        let second =
+            param("name: b\nin: path\nrequired: true\nschema:\n  type: integer\n  default: 7");
+
+        let path = expand_path("/{a}/{b}", &[first, second], &spec).unwrap();
+        assert_eq!(path, "/from-schema/7");
+    }
+
+    #[test]
+    fn expand_array_styles() {
+        let spec = empty_spec();
+        let simple = param("name: ids\nin: path\nrequired: true\nexample: [1, 2]");
+        let label = param(
+            "name: ids\nin: path\nrequired: true\nstyle: label\nexplode: true\nexample: [1, 2]",
+        );
+        let matrix = param("name: ids\nin: path\nrequired: true\nstyle: matrix\nexample: [1, 2]");
+
+        assert_eq!(expand_path("/{ids}", &[simple], &spec).unwrap(), "/1,2");
+        assert_eq!(expand_path("/{ids}", &[label], &spec).unwrap(), "/.1.2");
+        assert_eq!(
+            expand_path("/{ids}", &[matrix], &spec).unwrap(),
+            "/;ids=1,2"
+        );
+    }
+
+    #[test]
+    fn expand_object_explode() {
+        let spec = empty_spec();
+        let p =
+            param("name: point\nin: path\nrequired: true\nexplode: true\nexample:\n  x: 1\n  y: 2");
+
+        let path = expand_path("/{point}", &[p], &spec).unwrap();
+        assert_eq!(path, "/x=1,y=2");
+    }
+
+    #[test]
+    fn expand_fails_without_value_or_declaration() {
+        let spec = empty_spec();
+        let no_value = param("name: id\nin: path\nrequired: true");
+
+        assert!(expand_path("/users/{id}", &[no_value], &spec).is_err());
+        assert!(expand_path("/users/{id}", &[], &spec).is_err());
+    }
+
+    fn query(params: &[&str]) -> Vec<String> {
+        let spec = empty_spec();
+        let params: Vec<Parameter> = params.iter().map(|p| param(p)).collect();
+        query_combinations(&params, &spec).collect()
+    }
+
+    #[test]
+    fn no_query_parameters_yield_the_empty_query() {
+        assert_eq!(query(&[]), vec![String::new()]);
+    }
+
+    #[test]
+    fn required_query_parameters_are_always_sent() {
+        let c = query(&[
+            "name: page\nin: query\nrequired: true\nexample: 1",
+            "name: sort\nin: query\nexample: name",
+        ]);
+
+        // 2^1 - 1 optional subsets + the required-only variant
+        assert_eq!(c, vec!["page=1&sort=name", "page=1"]);
+    }
+
+    #[test]
+    fn optional_query_parameters_form_a_power_set() {
+        let c = query(&[
+            "name: a\nin: query\nexample: x",
+            "name: b\nin: query\nexample: y",
+        ]);
+
+        assert_eq!(c, vec!["a=x", "b=y", "a=x&b=y", ""]);
+    }
+
+    #[test]
+    fn query_combinations_are_lazy() {
+        let spec = empty_spec();
+        let params: Vec<Parameter> = (0..70)
+            .map(|i| param(&format!("name: q{i}\nin: query\nexample: {i}")))
+            .collect();
+
+        // 2^70 query strings could never be built upfront
+        let first: Vec<String> = query_combinations(&params, &spec).take(3).collect();
+        assert_eq!(first, ["q0=0", "q1=1", "q0=0&q1=1"]);
+    }
+
+    #[test]
+    fn query_array_and_object_styles() {
+        let spec = empty_spec();
+        let render = |yaml: &str| {
+            let p = param(yaml);
+            let v = example_value(&p, &spec).unwrap();
+            serialize_query(&p, &v)
+        };
+
+        assert_eq!(render("name: id\nin: query\nexample: [1, 2]"), "id=1&id=2");
+        assert_eq!(
+            render("name: id\nin: query\nexplode: false\nexample: [1, 2]"),
+            "id=1,2"
+        );
+        assert_eq!(
+            render("name: id\nin: query\nstyle: spaceDelimited\nexample: [1, 2]"),
+            "id=1%202"
+        );
+        assert_eq!(
+            render("name: id\nin: query\nstyle: pipeDelimited\nexample: [1, 2]"),
+            "id=1|2"
+        );
+        assert_eq!(
+            render("name: f\nin: query\nexample:\n  x: 1\n  y: 2"),
+            "x=1&y=2"
+        );
+        assert_eq!(
+            render("name: f\nin: query\nexplode: false\nexample:\n  x: 1\n  y: 2"),
+            "f=x,1,y,2"
+        );
+        assert_eq!(
+            render("name: f\nin: query\nstyle: deepObject\nexample:\n  x: 1\n  y: 2"),
+            "f[x]=1&f[y]=2"
+        );
+    }
+
+    fn headers(params: &[&str]) -> Vec<HeaderSet> {
+        let spec = empty_spec();
+        let params: Vec<Parameter> = params.iter().map(|p| param(p)).collect();
+        header_combinations(&params, &spec).collect()
+    }
+
+    fn header(name: &str, value: &str) -> (String, String) {
+        (name.to_owned(), value.to_owned())
+    }
+
+    #[test]
+    fn header_combinations_are_lazy() {
+        let spec = empty_spec();
+        let params: Vec<Parameter> = (0..70)
+            .map(|i| param(&format!("name: X-H{i}\nin: header\nexample: {i}")))
+            .collect();
+
+        // 2^70 header sets could never be built upfront
+        let first: Vec<HeaderSet> = header_combinations(&params, &spec).take(2).collect();
+        assert_eq!(first[0].len(), 70);
+        assert_eq!(first[1].len(), 69);
+        assert!(!first[1].contains(&header("X-H69", "69")));
+    }
+
+    #[test]
+    fn no_header_parameters_yield_one_empty_set() {
+        assert_eq!(headers(&[]), vec![HeaderSet::new()]);
+    }
+
+    #[test]
+    fn required_header_is_sent_with_each_example() {
+        let c = headers(&[
+            "name: X-Tenant-Id\nin: header\nrequired: true\nexamples:\n  a:\n    value: acme\n  b:\n    value: globex",
+        ]);
+
+        assert_eq!(
+            c,
+            vec![
+                vec![header("X-Tenant-Id", "acme")],
+                vec![header("X-Tenant-Id", "globex")],
+            ]
+        );
+    }
+
+    #[test]
+    fn optional_header_is_also_left_out() {
+        let c = headers(&[
+            "name: X-Tenant-Id\nin: header\nrequired: true\nexample: acme",
+            "name: Accept-Language\nin: header\nexample: it",
+        ]);
+
+        assert_eq!(
+            c,
+            vec![
+                vec![
+                    header("X-Tenant-Id", "acme"),
+                    header("Accept-Language", "it")
+                ],
+                vec![header("X-Tenant-Id", "acme")],
+            ]
+        );
+    }
+
+    #[test]
+    fn cookies_are_folded_into_one_header() {
+        let c = headers(&[
+            "name: session\nin: cookie\nrequired: true\nexample: abc",
+            "name: theme\nin: cookie\nrequired: true\nexample: dark",
+        ]);
+
+        assert_eq!(c, vec![vec![header("Cookie", "session=abc; theme=dark")]]);
+    }
+
+    #[test]
+    fn reserved_header_parameters_are_ignored() {
+        let c = headers(&[
+            "name: Accept\nin: header\nexample: text/plain",
+            "name: authorization\nin: header\nexample: secret",
+        ]);
+
+        assert_eq!(c, vec![HeaderSet::new()]);
+    }
+
+    #[test]
+    fn header_array_uses_simple_style() {
+        let c = headers(&["name: X-Ids\nin: header\nrequired: true\nexample: [1, 2]"]);
+
+        assert_eq!(c, vec![vec![header("X-Ids", "1,2")]]);
+    }
+
+    #[test]
+    fn query_parameters_do_not_touch_the_path() {
+        let spec = empty_spec();
+        let p = param("name: id\nin: query\nexample: 42");
+
+        let path = expand_path("/users", &[p], &spec).unwrap();
+        assert_eq!(path, "/users");
+    }
+}
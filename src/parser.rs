@@ -7,8 +7,14 @@ use serde_yaml_bw::Value;
 /// `exclusiveMinimum`/`exclusiveMaximum` as booleans, while 3.1 expects numbers,
 /// so the boolean form fails to deserialize. We strip those boolean flags before
 /// handing the document to `oas3` so older specs keep parsing. The flags only
-/// affect numeric bounds validation, which fiuto does not use when drilling
-/// payloads, so dropping them is lossless for our purposes.
+/// tighten numeric bounds, so at worst a synthesized value sits on an
+/// exclusive bound instead of just inside it. Likewise 3.0's `nullable: true`
+/// is dropped by `oas3`, so it is rewritten to a `"null"` entry in `type`,
+/// the 3.1 spelling, for `null` values to keep matching the schema.
+///
+/// An operation declaring `security: []` to make itself public is rewritten
+/// to the equivalent `security: [{}]`, because `oas3` reads an empty list as
+/// no `security` at all, which inherits the spec-level requirements instead.
 ///
 /// # Errors
 ///
@@ -19,6 +25,7 @@ pub fn parse_openapi(input: &str) -> Result<oas3::Spec, String> {
 
     let mut changed = false;
     downlevel_30(&mut doc, &mut changed);
+    mark_public_operations(&mut doc, &mut changed);
 
     // Only re-serialize when we actually patched something, so the untouched
     // common case feeds the original text straight to oas3.
@@ -37,8 +44,9 @@ fn format_yaml_error(e: &serde_yaml_bw::Error) -> String {
     )
 }
 
-/// Strip 3.0-only boolean `exclusiveMinimum`/`exclusiveMaximum` flags so the
-/// document deserializes under the 3.1 schema model.
+/// Strip 3.0-only boolean `exclusiveMinimum`/`exclusiveMaximum` flags and
+/// turn `nullable` into a `"null"` type, so the document deserializes under
+/// the 3.1 schema model.
 fn downlevel_30(value: &mut Value, changed: &mut bool) {
     match value {
         Value::Sequence(items) => items.iter_mut().for_each(|v| downlevel_30(v, changed)),
@@ -52,11 +60,63 @@ fn downlevel_30(value: &mut Value, changed: &mut bool) {
                     *changed = true;
                 }
             }
+            // A boolean tells the keyword apart from a property named so.
+            if matches!(map.get("nullable"), Some(Value::Bool(..)))
+                && let Some(Value::Bool(nullable, ..)) = map.remove("nullable")
+            {
+                *changed = true;
+                let null = || Value::String("null".to_owned(), None);
+                match map.get_mut("type") {
+                    Some(t @ Value::String(..)) if nullable => {
+                        let single = std::mem::replace(t, null());
+                        *t = [single, null()].into_iter().collect();
+                    }
+                    Some(Value::Sequence(types)) if nullable && !types.contains(&null()) => {
+                        types.push(null());
+                    }
+                    // Without a type, null is accepted already.
+                    _ => {}
+                }
+            }
         }
         _ => {}
     }
 }
 
+/// Keys of a path item holding an operation.
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Replace the `security: []` of operations with `security: [{}]`, the single
+/// requirement needing no scheme, so it survives deserialization.
+fn mark_public_operations(doc: &mut Value, changed: &mut bool) {
+    let Some(paths) = doc
+        .as_mapping_mut()
+        .and_then(|d| d.get_mut("paths"))
+        .and_then(Value::as_mapping_mut)
+    else {
+        return;
+    };
+
+    for item in paths.values_mut().filter_map(Value::as_mapping_mut) {
+        for method in METHODS {
+            let Some(security) = item
+                .get_mut(method)
+                .and_then(Value::as_mapping_mut)
+                .and_then(|op| op.get_mut("security"))
+                .and_then(Value::as_sequence_mut)
+            else {
+                continue;
+            };
+            if security.is_empty() {
+                security.push(Value::Mapping(serde_yaml_bw::Mapping::new()));
+                *changed = true;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::parse_openapi;
@@ -158,6 +218,33 @@ components:
         parse_openapi(spec).expect("boolean flag nested in array should be stripped");
     }
 
+    #[test]
+    fn nullable_from_30_becomes_a_null_type() {
+        let spec = spec_with_field("type: string\n          nullable: true");
+        let api = parse_openapi(&spec).unwrap();
+
+        let schemas = &api.components.as_ref().unwrap().schemas;
+        let thing = crate::collector::resolve_object_schema(&schemas["Thing"], &api).unwrap();
+        let field =
+            crate::collector::resolve_object_schema(&thing.properties["field"], &api).unwrap();
+        assert_eq!(field.is_nullable(), Some(true));
+    }
+
+    #[test]
+    fn property_named_nullable_is_kept() {
+        // The boolean flag forces the rewrite the property has to survive.
+        let spec = spec_with_field(
+            "type: object\n          properties:\n            nullable:\n              type: integer\n              minimum: 0\n              exclusiveMinimum: true",
+        );
+        let api = parse_openapi(&spec).unwrap();
+
+        let schemas = &api.components.as_ref().unwrap().schemas;
+        let thing = crate::collector::resolve_object_schema(&schemas["Thing"], &api).unwrap();
+        let field =
+            crate::collector::resolve_object_schema(&thing.properties["field"], &api).unwrap();
+        assert!(field.properties.contains_key("nullable"));
+    }
+
     #[test]
     fn valid_yaml_but_not_openapi_fails() {
         let err = parse_openapi("foo: bar\nbaz: 1").expect_err("non-openapi doc should fail");
@@ -167,6 +254,44 @@ components:
         );
     }
 
+    #[test]
+    fn empty_operation_security_is_kept_apart_from_absent() {
+        let spec = r#"
+openapi: 3.1.0
+info:
+  title: t
+  version: "1.0"
+security:
+  - bearerAuth: []
+paths:
+  /public:
+    get:
+      security: []
+      responses:
+        "200":
+          description: ok
+  /inherited:
+    get:
+      responses:
+        "200":
+          description: ok
+"#;
+        let api = parse_openapi(spec).unwrap();
+        let paths = api.paths.unwrap();
+
+        let public = &paths["/public"].get.as_ref().unwrap().security;
+        assert_eq!(public.len(), 1);
+        assert!(public[0].0.is_empty());
+        assert!(
+            paths["/inherited"]
+                .get
+                .as_ref()
+                .unwrap()
+                .security
+                .is_empty()
+        );
+    }
+
     #[test]
     fn empty_input_fails() {
         parse_openapi("").expect_err("empty input should fail");
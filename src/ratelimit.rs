@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+/// Longest wait honoured for one rate limit, whatever the server asks for.
+const MAX_WAIT: Duration = Duration::from_mins(5);
+
+/// First wait of the exponential backoff, doubled on each retry.
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Statuses a server answers with when the client should slow down and retry.
+pub const fn is_rate_limited(status: u16) -> bool {
+    matches!(status, 429 | 503)
+}
+
+/// How long the server asks to wait before retrying: `Retry-After` (delay in
+/// seconds or HTTP date) first, then the reset of the `RateLimit` headers.
+/// Header names are expected lowercase.
+pub fn retry_after(headers: &BTreeMap<String, String>, now: SystemTime) -> Option<Duration> {
+    let wait = headers
+        .get("retry-after")
+        .and_then(|v| {
+            let v = v.trim();
+            v.parse::<u64>().map(Duration::from_secs).ok().or_else(|| {
+                let at = httpdate::parse_http_date(v).ok()?;
+                // A date already gone means retrying right away.
+                Some(at.duration_since(now).unwrap_or_default())
+            })
+        })
+        .or_else(|| rate_limit(headers).map(|(_, reset)| reset))?;
+
+    Some(wait.min(MAX_WAIT))
+}
+
+/// How long until the quota resets, when the `RateLimit` headers say it is
+/// used up. Waiting that long spares the requests a 429 would reject.
+pub fn exhausted_for(headers: &BTreeMap<String, String>) -> Option<Duration> {
+    match rate_limit(headers)? {
+        (Some(0), reset) => Some(reset.min(MAX_WAIT)),
+        _ => None,
+    }
+}
+
+/// Wait before the retry number `attempt` (from 0) when the server gives no
+/// hint: 0.5s, 1s, 2s... up to [`MAX_WAIT`].
+pub fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .checked_mul(2u32.saturating_pow(attempt))
+        .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+}
+
+/// Reads the remaining quota and the seconds until it resets from the IETF
+/// `RateLimit` headers, in any of their drafts: separate
+/// `RateLimit-Remaining`/`RateLimit-Reset` fields, or a single `RateLimit`
+/// field like `limit=10, remaining=0, reset=5` or `"default";r=0;t=5`.
+fn rate_limit(headers: &BTreeMap<String, String>) -> Option<(Option<u64>, Duration)> {
+    let number = |v: &str| v.trim().trim_matches('"').parse::<u64>().ok();
+
+    if let Some(reset) = headers.get("ratelimit-reset").and_then(|v| number(v)) {
+        let remaining = headers.get("ratelimit-remaining").and_then(|v| number(v));
+        return Some((remaining, Duration::from_secs(reset)));
+    }
+
+    let field = headers.get("ratelimit")?;
+    let mut remaining = None;
+    let mut reset = None;
+    for param in field.split([',', ';']) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "remaining" | "r" => remaining = number(value),
+            "reset" | "t" => reset = number(value),
+            _ => {}
+        }
+    }
+
+    Some((remaining, Duration::from_secs(reset?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string_map;
+
+    #[test]
+    fn retry_after_in_seconds() {
+        let h = string_map(&[("retry-after", "7")]);
+        assert_eq!(
+            retry_after(&h, SystemTime::now()),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn retry_after_as_http_date() {
+        let now = httpdate::parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+
+        let h = string_map(&[("retry-after", "Sun, 06 Nov 1994 08:50:07 GMT")]);
+        assert_eq!(retry_after(&h, now), Some(Duration::from_secs(30)));
+
+        // a date in the past means no wait at all
+        let h = string_map(&[("retry-after", "Sun, 06 Nov 1994 08:00:00 GMT")]);
+        assert_eq!(retry_after(&h, now), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn retry_after_is_capped() {
+        let h = string_map(&[("retry-after", "86400")]);
+        assert_eq!(retry_after(&h, SystemTime::now()), Some(MAX_WAIT));
+    }
+
+    #[test]
+    fn rate_limit_reset_is_the_fallback() {
+        let h = string_map(&[("ratelimit-remaining", "0"), ("ratelimit-reset", "3")]);
+        assert_eq!(
+            retry_after(&h, SystemTime::now()),
+            Some(Duration::from_secs(3))
+        );
+
+        let h = string_map(&[("retry-after", "1"), ("ratelimit-reset", "3")]);
+        assert_eq!(
+            retry_after(&h, SystemTime::now()),
+            Some(Duration::from_secs(1))
+        );
+
+        assert_eq!(retry_after(&string_map(&[]), SystemTime::now()), None);
+    }
+
+    #[test]
+    fn structured_rate_limit_fields() {
+        let h = string_map(&[("ratelimit", "limit=10, remaining=0, reset=5")]);
+        assert_eq!(exhausted_for(&h), Some(Duration::from_secs(5)));
+
+        let h = string_map(&[("ratelimit", "\"default\";r=0;t=2")]);
+        assert_eq!(exhausted_for(&h), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn quota_left_means_no_wait() {
+        let h = string_map(&[("ratelimit-remaining", "4"), ("ratelimit-reset", "5")]);
+        assert_eq!(exhausted_for(&h), None);
+
+        let h = string_map(&[("ratelimit", "limit=10, remaining=9, reset=5")]);
+        assert_eq!(exhausted_for(&h), None);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+        assert_eq!(backoff(40), MAX_WAIT);
+    }
+
+    #[test]
+    fn only_429_and_503_are_rate_limits() {
+        assert!(is_rate_limited(429));
+        assert!(is_rate_limited(503));
+        assert!(!is_rate_limited(500));
+        assert!(!is_rate_limited(200));
+    }
+}
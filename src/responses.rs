@@ -0,0 +1,450 @@
+use oas3::spec::{ObjectSchema, Operation, Response};
+
+/// Finds the key of the documented response matching a status code: the exact
+/// code first, then its range (`4XX`), then `default`.
+pub fn documented_key(operation: &Operation, status: u16) -> Option<&str> {
+    let responses = operation.responses.as_ref()?;
+
+    let exact = status.to_string();
+    let range = format!("{}XX", status / 100);
+
+    responses
+        .keys()
+        .find(|k| **k == exact)
+        .or_else(|| responses.keys().find(|k| k.eq_ignore_ascii_case(&range)))
+        .or_else(|| responses.keys().find(|k| *k == "default"))
+        .map(String::as_str)
+}
+
+/// Resolves the response documented for a status code, see [`documented_key`].
+pub fn documented_response(
+    operation: &Operation,
+    status: u16,
+    spec: &oas3::Spec,
+) -> Option<Response> {
+    let key = documented_key(operation, status)?;
+    let response = operation.responses.as_ref()?.get(key)?;
+
+    response
+        .resolve(spec)
+        .inspect_err(|e| tracing::warn!("Cannot resolve response {key}: {e}"))
+        .ok()
+}
+
+/// The schema documented for the JSON content of a response, picked by the
+/// `Content-Type` it came with. There is none to check when that type is not
+/// JSON or not documented; without a `Content-Type`, the first JSON media type
+/// stands for it.
+fn json_schema(
+    response: &Response,
+    content_type: Option<&str>,
+    spec: &oas3::Spec,
+) -> Option<ObjectSchema> {
+    let (media_type, content) = match content_type {
+        Some(content_type) if !is_json(content_type) => return None,
+        Some(content_type) => response
+            .content
+            .iter()
+            .find(|(media_type, _)| essence(media_type) == essence(content_type))
+            .or_else(|| {
+                response
+                    .content
+                    .iter()
+                    .find(|(media_type, _)| covers(media_type, content_type))
+            })?,
+        None => response
+            .content
+            .iter()
+            .find(|(media_type, _)| is_json(media_type))?,
+    };
+    let schema = content.schema.as_ref()?;
+
+    crate::collector::resolve_object_schema(schema, spec)
+        .inspect_err(|e| tracing::warn!("Cannot resolve the {media_type} schema: {e}"))
+        .ok()
+}
+
+/// `application/json` and the `+json` structured syntax suffix, parameters
+/// such as `charset` aside.
+fn is_json(media_type: &str) -> bool {
+    let essence = essence(media_type);
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+/// Validates a response body against the schema documented for its status
+/// and `Content-Type`. A body that is not JSON at all is one violation at the
+/// root.
+fn body_violations(
+    operation: &Operation,
+    (status, content_type): (u16, Option<&str>),
+    body: &str,
+    spec: &oas3::Spec,
+) -> Vec<crate::Violation> {
+    let Some(schema) = documented_response(operation, status, spec)
+        .and_then(|r| json_schema(&r, content_type, spec))
+    else {
+        return vec![];
+    };
+
+    match serde_json::from_str(body) {
+        Ok(value) => crate::validator::validate(&value, &schema, spec),
+        Err(e) => vec![crate::Violation {
+            pointer: String::new(),
+            message: format!("body is not valid JSON: {e}"),
+        }],
+    }
+}
+
+/// Checks the documented response headers: the required ones must be there
+/// and any present one must match its schema. `headers` has lowercase names.
+fn header_mismatches(
+    response: &Response,
+    headers: &std::collections::BTreeMap<String, String>,
+    spec: &oas3::Spec,
+) -> Vec<String> {
+    let mut mismatches = vec![];
+
+    for (name, header) in &response.headers {
+        // A documented `Content-Type` header is ignored, as the spec says.
+        if name.eq_ignore_ascii_case("content-type") {
+            continue;
+        }
+        let header = match header.resolve(spec) {
+            Ok(h) => h,
+            Err(e) => {
+                tracing::warn!("Cannot resolve response header {name}: {e}");
+                continue;
+            }
+        };
+
+        let Some(raw) = headers.get(&name.to_ascii_lowercase()) else {
+            if header.required == Some(true) {
+                mismatches.push(format!("missing required header {name}"));
+            }
+            continue;
+        };
+
+        let Some(schema) = header
+            .schema
+            .as_ref()
+            .and_then(|s| crate::collector::resolve_object_schema(s, spec).ok())
+        else {
+            continue;
+        };
+        for v in crate::validator::validate(&header_value(raw, &schema, spec), &schema, spec) {
+            mismatches.push(format!("header {name}: {}", v.message));
+        }
+    }
+
+    mismatches
+}
+
+/// Reads a header with the `simple` style into the JSON value its schema
+/// expects. A value that cannot be read stays a string, so validation
+/// reports the type mismatch.
+fn header_value(raw: &str, schema: &ObjectSchema, spec: &oas3::Spec) -> serde_json::Value {
+    use oas3::spec::SchemaType as Type;
+
+    let raw = raw.trim();
+    let is = |t| schema.schema_type.as_ref().is_some_and(|ts| ts.contains(t));
+
+    if is(Type::Integer)
+        && let Ok(i) = raw.parse::<i64>()
+    {
+        return serde_json::json!(i);
+    }
+    if is(Type::Number)
+        && let Ok(n) = raw.parse::<f64>()
+        && let Some(n) = serde_json::Number::from_f64(n)
+    {
+        return serde_json::Value::Number(n);
+    }
+    if is(Type::Boolean)
+        && let Ok(b) = raw.parse::<bool>()
+    {
+        return serde_json::Value::Bool(b);
+    }
+    if is(Type::Array) {
+        let items = schema
+            .items
+            .as_deref()
+            .and_then(|i| crate::collector::resolve_object_schema(i, spec).ok())
+            .unwrap_or_default();
+        return serde_json::Value::Array(
+            raw.split(',')
+                .map(|v| header_value(v, &items, spec))
+                .collect(),
+        );
+    }
+
+    serde_json::Value::String(raw.to_owned())
+}
+
+/// Checks the `Content-Type` of a response against the media types documented
+/// for its status, ranges like `application/*` included. Nothing is checked
+/// when the response documents no content.
+fn content_type_mismatch(
+    response: &Response,
+    content_type: Option<&str>,
+    empty_body: bool,
+) -> Option<String> {
+    if response.content.is_empty() {
+        return None;
+    }
+
+    let documented = || {
+        response
+            .content
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let Some(content_type) = content_type else {
+        return (!empty_body).then(|| format!("no Content-Type, expected one of {}", documented()));
+    };
+
+    let matches = response.content.keys().any(|key| covers(key, content_type));
+
+    (!matches).then(|| {
+        format!(
+            "Content-Type {content_type} is not documented, expected one of {}",
+            documented()
+        )
+    })
+}
+
+/// Whether a documented media type, possibly a range like `application/*`,
+/// covers the `Content-Type` of a response.
+fn covers(documented: &str, content_type: &str) -> bool {
+    let documented = essence(documented);
+    let actual = essence(content_type);
+    let (actual_type, _) = actual.split_once('/').unwrap_or((&actual, ""));
+
+    documented == actual
+        || documented == "*/*"
+        || documented
+            .strip_suffix("/*")
+            .is_some_and(|range| range == actual_type)
+}
+
+/// The `type/subtype` of a media type, lowercased and without parameters.
+fn essence(media_type: &str) -> String {
+    media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Whether the operation documents the status code. An operation without any
+/// `responses` documents nothing to check against, so everything passes.
+pub fn is_documented(operation: &Operation, status: u16) -> bool {
+    operation
+        .responses
+        .as_ref()
+        .is_none_or(|r| r.is_empty() || documented_key(operation, status).is_some())
+}
+
+/// Checks a call of an operation against what its `responses` document.
+pub fn check(operation: &Operation, spec: &oas3::Spec, r: &mut crate::CallResult) {
+    // Without a response there is nothing to hold against the spec.
+    if r.outcome != crate::Outcome::Response {
+        return;
+    }
+
+    r.undocumented_status = !is_documented(operation, r.status_code);
+    if r.undocumented_status {
+        tracing::warn!("Undocumented status {} for {}", r.status_code, r.path);
+    }
+
+    let content_type = r.response_headers.get("content-type").map(String::as_str);
+    r.body_violations = body_violations(
+        operation,
+        (r.status_code, content_type),
+        &r.response_body,
+        spec,
+    );
+    for v in &r.body_violations {
+        tracing::warn!(
+            "Response body of {} at {:?}: {}",
+            r.path,
+            v.pointer,
+            v.message
+        );
+    }
+
+    if let Some(response) = documented_response(operation, r.status_code, spec) {
+        r.response_mismatches = header_mismatches(&response, &r.response_headers, spec);
+        r.response_mismatches.extend(content_type_mismatch(
+            &response,
+            content_type,
+            r.response_body.is_empty(),
+        ));
+    }
+    for m in &r.response_mismatches {
+        tracing::warn!("Response of {}: {m}", r.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string_map;
+
+    fn operation(yaml: &str) -> Operation {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn exact_code_wins_over_range_and_default() {
+        let op = operation(
+            "responses:\n  '404':\n    description: a\n  '4XX':\n    description: b\n  default:\n    description: c",
+        );
+
+        assert_eq!(documented_key(&op, 404), Some("404"));
+        assert_eq!(documented_key(&op, 422), Some("4XX"));
+        assert_eq!(documented_key(&op, 500), Some("default"));
+    }
+
+    #[test]
+    fn ranges_are_case_insensitive() {
+        let op = operation("responses:\n  '2xx':\n    description: ok");
+
+        assert!(is_documented(&op, 204));
+        assert!(!is_documented(&op, 400));
+    }
+
+    #[test]
+    fn undocumented_codes_are_flagged() {
+        let op = operation(
+            "responses:\n  '201':\n    description: created\n  '400':\n    description: bad",
+        );
+
+        assert!(is_documented(&op, 201));
+        assert!(is_documented(&op, 400));
+        assert!(!is_documented(&op, 200));
+        assert!(!is_documented(&op, 422));
+    }
+
+    #[test]
+    fn referenced_responses_are_resolved() {
+        let spec = crate::parse_openapi(
+            "openapi: 3.0.3\ninfo:\n  title: t\n  version: '1'\npaths: {}\ncomponents:\n  responses:\n    NotFound:\n      description: missing",
+        )
+        .unwrap();
+        let op = operation("responses:\n  '404':\n    $ref: '#/components/responses/NotFound'");
+
+        let r = documented_response(&op, 404, &spec).unwrap();
+        assert_eq!(r.description.as_deref(), Some("missing"));
+    }
+
+    #[test]
+    fn json_media_types() {
+        assert!(is_json("application/json"));
+        assert!(is_json("application/problem+json; charset=utf-8"));
+        assert!(!is_json("text/plain"));
+    }
+
+    #[test]
+    fn bodies_are_validated_against_the_documented_schema() {
+        let spec =
+            crate::parse_openapi(std::include_str!("./testdata/get_profile_response.yml")).unwrap();
+        let op = spec.paths.as_ref().unwrap()["/api/v1/profile"]
+            .get
+            .clone()
+            .unwrap();
+
+        let json = Some("application/json");
+        assert!(body_violations(&op, (200, json), r#"{"id": 1, "name": "x"}"#, &spec).is_empty());
+        assert_eq!(
+            body_violations(&op, (200, json), r#"{"id": "1", "name": "x"}"#, &spec)[0].pointer,
+            "/id"
+        );
+        assert_eq!(body_violations(&op, (200, json), "<html>", &spec).len(), 1);
+        assert_eq!(body_violations(&op, (200, None), "<html>", &spec).len(), 1);
+        // nothing documented for 404, so nothing to check
+        assert!(body_violations(&op, (404, json), "<html>", &spec).is_empty());
+    }
+
+    #[test]
+    fn bodies_are_validated_against_the_schema_of_their_content_type() {
+        let spec =
+            crate::parse_openapi(std::include_str!("./testdata/get_profile_response.yml")).unwrap();
+        let op = spec.paths.as_ref().unwrap()["/api/v1/profile"]
+            .get
+            .clone()
+            .unwrap();
+
+        let problem = Some("application/problem+json; charset=utf-8");
+        assert_eq!(
+            body_violations(&op, (400, problem), r#"{"id": 1}"#, &spec)[0].pointer,
+            ""
+        );
+        assert!(body_violations(&op, (400, problem), r#"{"title": "bad"}"#, &spec).is_empty());
+        // text is not checked against any schema
+        assert!(body_violations(&op, (400, Some("text/plain")), "bad", &spec).is_empty());
+    }
+
+    fn response(yaml: &str) -> Response {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn required_headers_must_be_present_and_valid() {
+        let spec =
+            crate::parse_openapi(std::include_str!("./testdata/get_profile_response.yml")).unwrap();
+        let r = response(
+            "description: ok\nheaders:\n  X-Rate-Limit:\n    required: true\n    schema:\n      type: integer\n  X-Trace:\n    schema:\n      type: string\n      format: uuid",
+        );
+
+        assert_eq!(
+            header_mismatches(&r, &string_map(&[]), &spec),
+            ["missing required header X-Rate-Limit"]
+        );
+        assert!(header_mismatches(&r, &string_map(&[("x-rate-limit", "100")]), &spec).is_empty());
+        assert_eq!(
+            header_mismatches(
+                &r,
+                &string_map(&[("x-rate-limit", "lots"), ("x-trace", "abc")]),
+                &spec
+            ),
+            [
+                "header X-Rate-Limit: expected integer, found string",
+                "header X-Trace: \"abc\" is not a valid uuid"
+            ]
+        );
+    }
+
+    #[test]
+    fn content_type_must_be_documented() {
+        let r = response("description: ok\ncontent:\n  application/json: {}\n  text/*: {}");
+
+        assert!(
+            content_type_mismatch(&r, Some("application/json; charset=utf-8"), false).is_none()
+        );
+        assert!(content_type_mismatch(&r, Some("text/plain"), false).is_none());
+        assert_eq!(
+            content_type_mismatch(&r, Some("application/xml"), false).unwrap(),
+            "Content-Type application/xml is not documented, expected one of application/json, text/*"
+        );
+        assert!(content_type_mismatch(&r, None, false).is_some());
+        assert!(content_type_mismatch(&r, None, true).is_none());
+    }
+
+    #[test]
+    fn responses_without_content_accept_any_content_type() {
+        let r = response("description: no content");
+        assert!(content_type_mismatch(&r, Some("text/html"), false).is_none());
+    }
+
+    #[test]
+    fn operations_without_responses_document_everything() {
+        let op = operation("summary: nothing");
+
+        assert!(is_documented(&op, 200));
+        assert!(is_documented(&op, 500));
+    }
+}
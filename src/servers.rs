@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use oas3::spec::Server;
+
+/// Picks the server requests go to: the one at index `selector` in
+/// `servers`, or the one whose description is `selector` (ignoring case),
+/// else the only one whose description contains it. The first server without
+/// a selector.
+pub fn pick<'a>(servers: &'a [Server], selector: Option<&str>) -> Result<&'a Server, String> {
+    let Some(selector) = selector else {
+        return servers
+            .first()
+            .ok_or_else(|| "no servers declared".to_owned());
+    };
+
+    if let Ok(index) = selector.parse::<usize>() {
+        return servers
+            .get(index)
+            .ok_or_else(|| format!("no server at index {index}, {} declared", servers.len()));
+    }
+
+    let described = |s: &&Server| s.description.as_deref().unwrap_or_default().to_lowercase();
+    let wanted = selector.to_lowercase();
+    if let Some(server) = servers.iter().find(|s| described(s) == wanted) {
+        return Ok(server);
+    }
+    match servers
+        .iter()
+        .filter(|s| described(s).contains(&wanted))
+        .collect::<Vec<_>>()[..]
+    {
+        [server] => Ok(server),
+        [] => Err(format!("no server described as {selector:?}")),
+        _ => Err(format!("several servers described as {selector:?}")),
+    }
+}
+
+/// Expands the `{variables}` of a server URL with the values in `vars`, or
+/// else each variable's `default`. A default missing from the variable's
+/// `enum` gives way to the first value of the `enum`.
+pub fn expand(server: &Server, vars: &BTreeMap<String, String>) -> String {
+    let mut url = server.url.clone();
+    for (name, variable) in &server.variables {
+        let allowed = &variable.substitutions_enum;
+        let value = match vars.get(name) {
+            Some(value) => {
+                if !allowed.is_empty() && !allowed.contains(value) {
+                    tracing::warn!("{value:?} is not among the values of {name}: {allowed:?}");
+                }
+                value
+            }
+            None if allowed.is_empty() || allowed.contains(&variable.default) => &variable.default,
+            None => &allowed[0],
+        };
+        url = url.replace(&format!("{{{name}}}"), value);
+    }
+
+    if url.contains('{') {
+        tracing::warn!("Server URL {url} still has undeclared variables");
+    }
+    // Paths start with a slash already.
+    url.trim_end_matches('/').to_owned()
+}
+
+/// The base URL of an operation declaring its own `servers`, or of its path
+/// item. The selector picks among them too when it matches, else the first
+/// one is taken.
+pub fn for_operation(
+    servers: &[Server],
+    (selector, vars): (Option<&str>, &BTreeMap<String, String>),
+) -> Option<String> {
+    let server = pick(servers, selector)
+        .or_else(|_| pick(servers, None))
+        .ok()?;
+    Some(expand(server, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::string_map;
+
+    fn servers(yaml: &str) -> Vec<Server> {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    const REGIONAL: &str = r"
+- url: https://{region}.api.example.com/{version}/
+  description: Production
+  variables:
+    region:
+      default: us
+      enum: [us, eu]
+    version:
+      default: v2
+- url: http://localhost:8000
+  description: Local development
+- url: http://staging.example.com
+  description: Staging
+";
+
+    #[test]
+    fn every_variable_is_expanded() {
+        let s = servers(REGIONAL);
+        assert_eq!(
+            expand(&s[0], &string_map(&[])),
+            "https://us.api.example.com/v2"
+        );
+        assert_eq!(
+            expand(&s[0], &string_map(&[("region", "eu"), ("version", "v3")])),
+            "https://eu.api.example.com/v3"
+        );
+    }
+
+    #[test]
+    fn default_outside_the_enum_gives_way_to_it() {
+        let s = servers(
+            r"
+- url: https://{region}.example.com
+  variables:
+    region:
+      default: moon
+      enum: [eu, us]
+",
+        );
+        assert_eq!(expand(&s[0], &string_map(&[])), "https://eu.example.com");
+    }
+
+    #[test]
+    fn servers_are_picked_by_index_or_description() {
+        let s = servers(REGIONAL);
+        assert_eq!(pick(&s, None).unwrap().url, s[0].url);
+        assert_eq!(
+            pick(&s, Some("2")).unwrap().url,
+            "http://staging.example.com"
+        );
+        assert_eq!(
+            pick(&s, Some("local development")).unwrap().url,
+            "http://localhost:8000"
+        );
+        assert_eq!(
+            pick(&s, Some("stag")).unwrap().url,
+            "http://staging.example.com"
+        );
+
+        assert!(pick(&s, Some("3")).is_err());
+        assert!(pick(&s, Some("qa")).is_err());
+        // both "Production" and "Local development" contain it
+        assert!(pick(&s, Some("o")).is_err());
+        assert!(pick(&[], None).is_err());
+    }
+
+    #[test]
+    fn operation_servers_fall_back_to_their_first() {
+        let s = servers(REGIONAL);
+        assert_eq!(
+            for_operation(&s, (Some("qa"), &string_map(&[]))).as_deref(),
+            Some("https://us.api.example.com/v2")
+        );
+        assert_eq!(for_operation(&[], (None, &string_map(&[]))), None);
+    }
+}
@@ -1,39 +1,236 @@
-pub fn do_it(
-    point: &std::rc::Rc<std::cell::RefCell<crate::digger::Node>>,
-) -> Vec<std::collections::HashMap<String, serde_json::Value>> {
-    let mut properties = vec![];
+use crate::digger::{Kind, Node};
+
+/// Longest array generated for a `minItems`/`maxItems` boundary. Bounds past it
+/// are left out instead of building huge payloads.
+pub const MAX_BOUNDARY_ITEMS: u64 = 64;
+
+/// Most tuples a covering array keeps track of. Past it the strength is
+/// lowered, since the tuples of a high strength over many properties grow
+/// like C(n, t)·L^t.
+const MAX_TUPLES: usize = 1 << 16;
+
+/// Which payloads to build out of the digger tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Every subset of the properties, valid or not.
+    #[default]
+    Exhaustive,
+    /// Only payloads the schema accepts: every required property is always
+    /// present and the optional ones are combined.
+    Valid,
+    /// Negative cases: the complete payload with exactly one required
+    /// property left out, at any nesting level.
+    MissingRequired,
+}
+
+/// How many of the combinations to keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coverage {
+    /// The whole power set of the properties.
+    #[default]
+    Full,
+    /// Only enough combinations to cover every interaction of this many
+    /// properties, `TWay(2)` being pairwise.
+    TWay(usize),
+}
+
+/// Everything that shapes the payloads built by the shuffler.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub mode: Mode,
+    pub coverage: Coverage,
+}
+
+/// Builds the payloads to drill from the digger tree in the given mode: the
+/// combinations of an object body or of a top-level composition (plus the
+/// empty object when exhaustive), or the variants of a top-level array. They
+/// are built lazily and at most `max` of them, nested values included, so a
+/// cap on the requests also bounds the work on large schemas.
+pub fn payloads(
+    root: &std::rc::Rc<std::cell::RefCell<Node>>,
+    opts: Options,
+    max: usize,
+) -> impl Iterator<Item = serde_json::Value> + use<> {
+    let kind = root.borrow().kind;
+    let payloads: Box<dyn Iterator<Item = serde_json::Value>> =
+        if opts.mode == Mode::MissingRequired {
+            let mut payloads = vec![];
+            for v in missing_required(root) {
+                if !payloads.contains(&v) {
+                    payloads.push(v);
+                }
+            }
+            Box::new(payloads.into_iter())
+        } else {
+            match kind {
+                Kind::Array { .. } => Box::new(array_variants(root, opts, max).into_iter()),
+                Kind::Choice => Box::new(choice_variants(root, opts, max).into_iter()),
+                Kind::Object | Kind::Leaf => Box::new(
+                    combine(root, opts, max)
+                        .map(|c| serde_json::Value::Object(c.into_iter().collect())),
+                ),
+            }
+        };
+
+    // add empty payload to object bodies, in valid mode it is already there
+    // when allowed
+    let empty = (opts.mode == Mode::Exhaustive && !matches!(kind, Kind::Array { .. }))
+        .then(|| serde_json::Value::Object(serde_json::Map::new()));
+
+    payloads.chain(empty).take(max)
+}
+
+/// Tells whether the schema described by the tree accepts `value`: objects
+/// carry all their required properties, arrays respect their bounds and a
+/// composition matches one of its alternatives. Leaves come from the spec, so
+/// their values are trusted.
+pub fn is_valid(node: &std::rc::Rc<std::cell::RefCell<Node>>, value: &serde_json::Value) -> bool {
+    let n = node.borrow();
+    match n.kind {
+        Kind::Leaf => true,
+        Kind::Object => value.as_object().is_some_and(|obj| {
+            n.children.iter().all(|c| {
+                let c_ref = c.borrow();
+                obj.get(&c_ref.name)
+                    .map_or(!c_ref.required, |v| is_valid(c, v))
+            })
+        }),
+        Kind::Array {
+            min_items,
+            max_items,
+        } => value.as_array().is_some_and(|items| {
+            let len = items.len() as u64;
+            min_items.is_none_or(|min| len >= min)
+                && max_items.is_none_or(|max| len <= max)
+                && n.children
+                    .first()
+                    .is_none_or(|item| items.iter().all(|v| is_valid(item, v)))
+        }),
+        Kind::Choice => n.children.iter().any(|alt| is_valid(alt, value)),
+    }
+}
+
+/// Lazily combines the children of an object node through their power set.
+pub fn combinations(
+    point: &std::rc::Rc<std::cell::RefCell<Node>>,
+) -> impl Iterator<Item = Combination> + use<> {
+    combine(point, Options::default(), usize::MAX)
+}
 
-    let mut sub_properties = std::collections::HashMap::new();
+#[cfg(test)]
+pub fn do_it(point: &std::rc::Rc<std::cell::RefCell<Node>>) -> Vec<Combination> {
+    combinations(point).collect()
+}
+
+/// Combinations of the children of an object node. Exhaustive mode goes
+/// through the whole power set; valid mode keeps the required children in
+/// every combination and only combines the optional ones. With a t-way
+/// coverage the power set is replaced by a covering array. The values of a
+/// nested child are capped at `max`.
+fn combine(
+    point: &std::rc::Rc<std::cell::RefCell<Node>>,
+    opts: Options,
+    max: usize,
+) -> Box<dyn Iterator<Item = Combination>> {
+    // One factor per child: its name, whether it may be left out and the
+    // values it can take, the first being the one used in the power set.
+    let mut factors = vec![];
 
     // this check if all kids are leaves, becuse if only one has children, we need to go deeper.
     // otherwise, we can generate the combinations.
     for c in &point.borrow().children {
         let n = c.borrow();
-        properties.push((n.name.clone(), n.value.clone()));
 
-        if !n.children.is_empty() {
-            let m = do_it(c);
-            sub_properties.insert(c.borrow().name.clone(), m);
+        let values = match n.kind {
+            // The single full element of an array and the first complete
+            // alternative of a composition go through the power set like a
+            // leaf, the other variants are combined like a nested object's.
+            Kind::Array { .. } | Kind::Choice => partial_variants(c, opts, max),
+            Kind::Object => {
+                let mut variants = partial_variants(c, opts, max);
+                // A null object is never valid, so the complete one stands
+                // for it in valid mode.
+                let first = if opts.mode == Mode::Valid {
+                    let full = complete_value(c);
+                    variants.retain(|v| *v != full);
+                    full
+                } else {
+                    n.value.clone()
+                };
+                variants.insert(0, first);
+                variants
+            }
+            Kind::Leaf => vec![n.value.clone()],
+        };
+        if values.is_empty() {
+            continue;
         }
+
+        let optional = opts.mode != Mode::Valid || !n.required;
+        factors.push((n.name.clone(), optional, values));
     }
 
-    let mut combs = vec![];
+    match opts.coverage {
+        Coverage::Full => Box::new(power_set(factors, opts.mode)),
+        Coverage::TWay(strength) => {
+            Box::new(covered(&factors, strength, opts.mode, max).into_iter())
+        }
+    }
+}
 
-    let total_combinations = (1 << properties.len()) - 1;
-    for mask in 1..=total_combinations {
-        let mut c = std::collections::HashMap::new();
-        for (i, p) in properties.iter().enumerate() {
-            if (mask & (1 << i)) == 0 {
-                continue;
-            }
+type Factor = (String, bool, Vec<serde_json::Value>);
 
-            c.insert(p.0.clone(), p.1.clone());
-        }
+type Combination = std::collections::HashMap<String, serde_json::Value>;
 
-        combs.push(c);
+/// Goes lazily through the subsets of the optional factors on top of the
+/// required ones, then through the same subsets with each other value of a
+/// factor swapped in. Masks hold one bit per optional factor, so only the
+/// first `usize::BITS` of them are combined.
+fn power_set(factors: Vec<Factor>, mode: Mode) -> impl Iterator<Item = Combination> {
+    let mut always = Combination::new();
+    let mut properties = vec![];
+    let mut sub_properties = vec![];
+
+    for (name, optional, mut values) in factors {
+        let others = values.split_off(1);
+        let Some(first) = values.pop() else {
+            continue;
+        };
+        if !others.is_empty() {
+            sub_properties.push((name.clone(), others));
+        }
+        if optional {
+            properties.push((name, first));
+        } else {
+            always.insert(name, first);
+        }
     }
 
-    let mut to_push = vec![];
+    // The empty subset only makes sense on top of the required properties.
+    let first_mask = usize::from(mode != Mode::Valid);
+    let last_mask = u32::try_from(properties.len())
+        .ok()
+        .and_then(|bits| 1usize.checked_shl(bits))
+        .map_or(usize::MAX, |n| n - 1);
+    let masks = first_mask..=last_mask;
+
+    let subset = std::rc::Rc::new(move |mask: usize| {
+        let mut c = always.clone();
+        for (i, p) in properties.iter().enumerate() {
+            let set = u32::try_from(i)
+                .ok()
+                .and_then(|i| mask.checked_shr(i))
+                .is_some_and(|m| m & 1 == 1);
+            if set {
+                c.insert(p.0.clone(), p.1.clone());
+            }
+        }
+        c
+    });
+    let combs = masks.clone().map({
+        let subset = std::rc::Rc::clone(&subset);
+        move |mask| subset(mask)
+    });
 
     // for every combination, we need to add the sub properties to it.
     // if you have for example:
@@ -44,27 +241,368 @@ pub fn do_it(
     // - [null]
     // - [object (with all possible combinations)]
     // [string, object (with all possible combinations)]
-    for c in &combs {
-        for (k, v) in &sub_properties {
-            v.iter()
-                .map(|variant| serde_json::to_value(variant).unwrap())
-                .for_each(|vv| {
+    // Without sub properties the masks are not walked a second time.
+    let swaps = (!sub_properties.is_empty()).then_some(masks);
+    let to_push = swaps.into_iter().flatten().flat_map(move |mask| {
+        let c = subset(mask);
+        sub_properties
+            .iter()
+            .flat_map(|(k, v)| {
+                v.iter().map(|vv| {
                     let mut h = c.clone();
-                    h.insert(k.clone(), vv);
-                    to_push.push(h);
-                });
+                    h.insert(k.clone(), vv.clone());
+                    h
+                })
+            })
+            .collect::<Vec<_>>()
+    });
+
+    combs.chain(to_push)
+}
+
+/// Combinations covering every interaction of `strength` children: each
+/// child is a factor whose levels are being absent (when optional) and each
+/// of its values. At most `max` combinations are built.
+fn covered(factors: &[Factor], strength: usize, mode: Mode, max: usize) -> Vec<Combination> {
+    let levels: Vec<usize> = factors
+        .iter()
+        .map(|(_, optional, values)| values.len() + usize::from(*optional))
+        .collect();
+
+    // One more row than needed, as the empty one may be filtered out below.
+    covering_array(&levels, strength, max.saturating_add(1))
+        .into_iter()
+        .map(|row| {
+            factors
+                .iter()
+                .zip(row)
+                .filter_map(|((name, optional, values), level)| {
+                    let index = level.checked_sub(usize::from(*optional))?;
+                    Some((name.clone(), values[index].clone()))
+                })
+                .collect::<std::collections::HashMap<_, _>>()
+        })
+        // The empty object is added on its own in exhaustive mode.
+        .filter(|c| mode == Mode::Valid || !c.is_empty())
+        .take(max)
+        .collect()
+}
+
+/// Builds the rows of a covering array: every assignment of levels to any
+/// `strength` factors shows up in at least one row. `levels[i]` is how many
+/// levels factor `i` has. Rows are built greedily, each one seeded with an
+/// uncovered tuple and then filled with the levels covering the most tuples
+/// still missing, which keeps the result deterministic. It is not minimal, but
+/// close: 20 two-level factors need 11 rows for pairwise coverage. No more
+/// than `max` rows are built, and the strength is lowered until the tuples to
+/// cover fit in [`MAX_TUPLES`].
+fn covering_array(levels: &[usize], strength: usize, max: usize) -> Vec<Vec<usize>> {
+    if levels.is_empty() {
+        return vec![vec![]];
+    }
+    let wanted = strength.clamp(1, levels.len());
+    let mut strength = wanted;
+    while strength > 1 && tuple_count(levels, strength) > MAX_TUPLES {
+        strength -= 1;
+    }
+    if strength < wanted {
+        tracing::warn!("Lowering the coverage strength from {wanted} to {strength}");
+    }
+
+    let mut uncovered = std::collections::BTreeSet::new();
+    for subset in subsets(levels.len(), strength) {
+        let mut assignments: Vec<Vec<(usize, usize)>> = vec![vec![]];
+        for &f in &subset {
+            assignments = assignments
+                .into_iter()
+                .flat_map(|a| {
+                    (0..levels[f]).map(move |l| {
+                        let mut a = a.clone();
+                        a.push((f, l));
+                        a
+                    })
+                })
+                .collect();
+        }
+        uncovered.extend(assignments);
+    }
+
+    let mut rows = vec![];
+    while rows.len() < max
+        && let Some(seed) = uncovered.first().cloned()
+    {
+        let mut row: Vec<Option<usize>> = vec![None; levels.len()];
+        for (f, l) in seed {
+            row[f] = Some(l);
+        }
+
+        for f in 0..levels.len() {
+            if row[f].is_some() {
+                continue;
+            }
+            // Prefer the level covering the most tuples right away, then the
+            // one leaving the most tuples still coverable by the rest of the row.
+            let best = (0..levels[f])
+                .max_by_key(|&l| {
+                    let mut now = 0;
+                    let mut later = 0;
+                    for tuple in uncovered.iter().filter(|tuple| tuple.contains(&(f, l))) {
+                        if tuple.iter().all(|&(g, m)| g == f || row[g] == Some(m)) {
+                            now += 1;
+                        }
+                        if tuple
+                            .iter()
+                            .all(|&(g, m)| g == f || row[g].is_none_or(|r| r == m))
+                        {
+                            later += 1;
+                        }
+                    }
+                    (now, later, std::cmp::Reverse(l))
+                })
+                .unwrap_or(0);
+            row[f] = Some(best);
+        }
+
+        let row: Vec<usize> = row.into_iter().map(Option::unwrap_or_default).collect();
+        uncovered.retain(|tuple| !tuple.iter().all(|&(f, l)| row[f] == l));
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// How many tuples cover every assignment of levels to any `strength` factors,
+/// saturating at `usize::MAX`: the sum over those subsets of the product of
+/// their levels, added up one factor at a time.
+fn tuple_count(levels: &[usize], strength: usize) -> usize {
+    // counts[k] is the number of tuples of k factors among those seen so far.
+    let mut counts = vec![0usize; strength + 1];
+    counts[0] = 1;
+    for &l in levels {
+        for k in (1..=strength).rev() {
+            counts[k] = counts[k].saturating_add(counts[k - 1].saturating_mul(l));
+        }
+    }
+    counts[strength]
+}
+
+/// Every subset of `size` indices out of `0..n`, in lexicographic order.
+fn subsets(n: usize, size: usize) -> Vec<Vec<usize>> {
+    fn extend(
+        from: usize,
+        n: usize,
+        size: usize,
+        current: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if current.len() == size {
+            out.push(current.clone());
+            return;
+        }
+        for i in from..n {
+            current.push(i);
+            extend(i + 1, n, size, current, out);
+            current.pop();
+        }
+    }
+
+    let mut out = vec![];
+    extend(0, n, size, &mut vec![], &mut out);
+    out
+}
+
+/// Builds the variants of an array node. The first one always holds a single
+/// complete element; then come the empty array, a multi-element one and the
+/// arrays sitting on and just past the `minItems`/`maxItems` bounds. When the
+/// items are objects, each of their own combinations is also sent alone. In
+/// valid mode the variants breaking the bounds are left out.
+fn array_variants(
+    node: &std::rc::Rc<std::cell::RefCell<Node>>,
+    opts: Options,
+    max: usize,
+) -> Vec<serde_json::Value> {
+    let n = node.borrow();
+    let Kind::Array {
+        min_items,
+        max_items,
+    } = n.kind
+    else {
+        return vec![];
+    };
+    let Some(item) = n.children.first() else {
+        return vec![];
+    };
+
+    let full = complete_value(item);
+
+    let mut lengths = std::collections::BTreeSet::from([0, 2]);
+    if let Some(min) = min_items {
+        lengths.extend([min.saturating_sub(1), min]);
+    }
+    if let Some(max) = max_items {
+        lengths.extend([max, max.saturating_add(1)]);
+    }
+    lengths.remove(&1);
+
+    let mut variants = vec![serde_json::Value::Array(vec![full.clone()])];
+    variants.extend(
+        lengths
+            .into_iter()
+            .filter(|len| *len <= MAX_BOUNDARY_ITEMS)
+            .filter_map(|len| usize::try_from(len).ok())
+            .map(|len| serde_json::Value::Array(vec![full.clone(); len])),
+    );
+
+    if matches!(item.borrow().kind, Kind::Object | Kind::Choice) {
+        for v in partial_variants(item, opts, max) {
+            let v = serde_json::Value::Array(vec![v]);
+            if v != variants[0] {
+                variants.push(v);
+            }
         }
     }
 
-    for t in to_push {
-        combs.push(t);
+    drop(n);
+    if opts.mode == Mode::Valid {
+        variants.retain(|v| is_valid(node, v));
     }
 
-    combs
+    variants
+}
+
+/// Builds the variants of a composition: every alternative complete first,
+/// then the combinations of each alternative, without duplicates.
+fn choice_variants(
+    node: &std::rc::Rc<std::cell::RefCell<Node>>,
+    opts: Options,
+    max: usize,
+) -> Vec<serde_json::Value> {
+    let n = node.borrow();
+
+    let mut variants: Vec<serde_json::Value> = n.children.iter().map(complete_value).collect();
+    for alt in &n.children {
+        for v in partial_variants(alt, opts, max) {
+            if !variants.contains(&v) {
+                variants.push(v);
+            }
+        }
+    }
+
+    variants
+}
+
+/// Every value a node can take when drilled on its own, an object's
+/// combinations being capped at `max`.
+fn partial_variants(
+    node: &std::rc::Rc<std::cell::RefCell<Node>>,
+    opts: Options,
+    max: usize,
+) -> Vec<serde_json::Value> {
+    let kind = node.borrow().kind;
+    match kind {
+        Kind::Array { .. } => array_variants(node, opts, max),
+        Kind::Choice => choice_variants(node, opts, max),
+        Kind::Object => combine(node, opts, max)
+            .take(max)
+            .map(|v| serde_json::Value::Object(v.into_iter().collect()))
+            .collect(),
+        Kind::Leaf => vec![node.borrow().value.clone()],
+    }
+}
+
+/// The complete value of a node with one required property removed, once per
+/// required property at every nesting level.
+fn missing_required(node: &std::rc::Rc<std::cell::RefCell<Node>>) -> Vec<serde_json::Value> {
+    let n = node.borrow();
+    match n.kind {
+        Kind::Leaf => vec![],
+        Kind::Array { .. } => n
+            .children
+            .first()
+            .map(|item| {
+                missing_required(item)
+                    .into_iter()
+                    .map(|v| serde_json::Value::Array(vec![v]))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Kind::Choice => n.children.iter().flat_map(missing_required).collect(),
+        Kind::Object => {
+            let serde_json::Value::Object(full) = complete_value(node) else {
+                return vec![];
+            };
+
+            let mut variants = vec![];
+            for c in &n.children {
+                let name = c.borrow().name.clone();
+                if c.borrow().required {
+                    let mut v = full.clone();
+                    v.remove(&name);
+                    variants.push(serde_json::Value::Object(v));
+                }
+                for nested in missing_required(c) {
+                    let mut v = full.clone();
+                    v.insert(name.clone(), nested);
+                    variants.push(serde_json::Value::Object(v));
+                }
+            }
+            variants
+        }
+    }
+}
+
+/// Value of a node with every property present, used as the array element. A
+/// composition takes its first alternative.
+fn complete_value(node: &std::rc::Rc<std::cell::RefCell<Node>>) -> serde_json::Value {
+    let n = node.borrow();
+    match n.kind {
+        Kind::Array { .. } => {
+            serde_json::Value::Array(n.children.iter().map(complete_value).collect())
+        }
+        Kind::Object => serde_json::Value::Object(
+            n.children
+                .iter()
+                .map(|c| (c.borrow().name.clone(), complete_value(c)))
+                .collect(),
+        ),
+        Kind::Choice => n
+            .children
+            .first()
+            .map_or(serde_json::Value::Null, complete_value),
+        Kind::Leaf => n.value.clone(),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    fn mode(mode: crate::shuffler::Mode) -> crate::shuffler::Options {
+        crate::shuffler::Options {
+            mode,
+            ..crate::shuffler::Options::default()
+        }
+    }
+
+    fn pairwise() -> crate::shuffler::Options {
+        crate::shuffler::Options {
+            coverage: crate::shuffler::Coverage::TWay(2),
+            ..crate::shuffler::Options::default()
+        }
+    }
+
+    /// Whether every level of every `strength` factors shows up in a row.
+    fn covers(rows: &[Vec<usize>], levels: &[usize], strength: usize) -> bool {
+        crate::shuffler::subsets(levels.len(), strength)
+            .iter()
+            .all(|subset| {
+                let wanted: usize = subset.iter().map(|&f| levels[f]).product();
+                let seen: std::collections::HashSet<Vec<usize>> = rows
+                    .iter()
+                    .map(|r| subset.iter().map(|&f| r[f]).collect())
+                    .collect();
+                seen.len() == wanted
+            })
+    }
+
     #[test]
     fn one_level_properties() {
         // here we have:
@@ -78,7 +616,7 @@ mod tests {
 
         assert_eq!(c.len(), 7);
 
-        let zero = c.get(0).unwrap();
+        let zero = c.first().unwrap();
         assert!(zero.contains_key("email"));
 
         let one = c.get(1).unwrap();
@@ -106,6 +644,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::too_many_lines)]
     fn one_as_object() {
         // here we have:
         // - hq -> address, postal_code, city, state_region, country
@@ -113,11 +652,11 @@ mod tests {
         let root = crate::digger::load_nested();
         let c = crate::shuffler::do_it(&root);
 
-        println!("{:#?}", c);
+        println!("{c:#?}");
 
         assert_eq!(c.len(), 32);
 
-        let zero = c.get(0).unwrap();
+        let zero = c.first().unwrap();
         assert!(zero.contains_key("hq"));
         assert_eq!(zero.get("hq").unwrap(), &serde_json::Value::Null);
 
@@ -363,6 +902,334 @@ mod tests {
         assert_eq!(c.len(), 7);
     }
 
+    #[test]
+    fn array_property_variants_are_combined_with_siblings() {
+        // here we have:
+        // - name (string)
+        // - tags (array, minItems 1, maxItems 3)
+        let root = crate::digger::load_arrays();
+        let c = crate::shuffler::do_it(&root);
+
+        // 2^2 - 1 subsets carry the single element array; the other lengths
+        // (0, 2, 3, 4) are then set on each of those subsets: 3 + 3 * 4
+        assert_eq!(c.len(), 15);
+
+        let mut lengths: Vec<usize> = c
+            .iter()
+            .filter_map(|m| m.get("tags"))
+            .map(|t| t.as_array().unwrap().len())
+            .collect();
+        lengths.sort_unstable();
+        lengths.dedup();
+        assert_eq!(lengths, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn top_level_array_payloads() {
+        let root = crate::digger::load_array_body();
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), usize::MAX)
+            .collect::<Vec<_>>();
+
+        let full = serde_json::json!({"email": "federico@fiuto.io", "role": "admin"});
+        // single complete element first, then empty and multi-element arrays,
+        // then one single-element array per partial item combination
+        assert_eq!(
+            p,
+            vec![
+                serde_json::json!([full]),
+                serde_json::json!([]),
+                serde_json::json!([full, full]),
+                serde_json::json!([{"email": "federico@fiuto.io"}]),
+                serde_json::json!([{"role": "admin"}]),
+            ]
+        );
+    }
+
+    #[test]
+    fn huge_max_items_does_not_overflow() {
+        let root = crate::digger::load_array_body();
+        root.borrow_mut().kind = crate::digger::Kind::Array {
+            min_items: None,
+            max_items: Some(u64::MAX),
+        };
+
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), usize::MAX)
+            .collect::<Vec<_>>();
+        // the bound itself is past the boundary limit, so it is left out
+        assert!(p.iter().all(|v| v.as_array().unwrap().len() <= 2));
+    }
+
+    #[test]
+    fn one_of_payloads_start_with_each_complete_alternative() {
+        let root = crate::digger::load_one_of();
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), usize::MAX)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            p[..2],
+            [
+                serde_json::json!({"petType": "cat", "name": "Fido", "indoor": true}),
+                serde_json::json!({"petType": "dog", "name": "Fido", "bark": "woof"}),
+            ]
+        );
+        // the partial combinations of both alternatives, `{"name": "Fido"}`
+        // only once, then the empty object
+        assert_eq!(p.len(), 14);
+        assert!(p.contains(&serde_json::json!({"bark": "woof"})));
+        assert_eq!(p.last().unwrap(), &serde_json::json!({}));
+    }
+
+    #[test]
+    fn choice_property_variants_are_combined_with_siblings() {
+        let choice = root_with_leaves(&[
+            ("option 1", serde_json::json!("a")),
+            ("option 2", serde_json::json!(1)),
+        ]);
+        choice.borrow_mut().name = "id".to_owned();
+        choice.borrow_mut().kind = crate::digger::Kind::Choice;
+        let root = root_with_leaves(&[]);
+        root.borrow_mut().children.push(choice);
+
+        let c = crate::shuffler::do_it(&root);
+        // {id: "a"} from the power set, then {id: 1} from the other alternative
+        assert_eq!(c.len(), 2);
+        assert_eq!(c[0]["id"], "a");
+        assert_eq!(c[1]["id"], 1);
+    }
+
+    #[test]
+    fn valid_mode_always_keeps_required_properties() {
+        // every login property is required
+        let root = crate::digger::load_flat_level();
+        let p = crate::shuffler::payloads(&root, mode(crate::shuffler::Mode::Valid), usize::MAX)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            p,
+            vec![serde_json::json!({
+                "email": "federico@fiuto.io",
+                "org": "fiuto",
+                "password": "CiaoCiao94!%"
+            })]
+        );
+    }
+
+    #[test]
+    fn valid_mode_combines_only_optional_properties() {
+        let root = root_with_leaves(&[
+            ("id", serde_json::json!(1)),
+            ("a", serde_json::json!("x")),
+            ("b", serde_json::json!("y")),
+        ]);
+        root.borrow_mut().kind = crate::digger::Kind::Object;
+        root.borrow().children[0].borrow_mut().required = true;
+
+        let p = crate::shuffler::payloads(&root, mode(crate::shuffler::Mode::Valid), usize::MAX)
+            .collect::<Vec<_>>();
+        // the required id alone, then with every subset of the optional ones
+        assert_eq!(p.len(), 4);
+        assert!(p.iter().all(|v| v["id"] == 1));
+        assert!(p.iter().all(|v| crate::shuffler::is_valid(&root, v)));
+    }
+
+    #[test]
+    fn missing_required_mode_drops_one_property_at_a_time() {
+        let root = crate::digger::load_flat_level();
+        let p = crate::shuffler::payloads(
+            &root,
+            mode(crate::shuffler::Mode::MissingRequired),
+            usize::MAX,
+        )
+        .collect::<Vec<_>>();
+
+        assert_eq!(p.len(), 3);
+        for v in &p {
+            assert_eq!(v.as_object().unwrap().len(), 2);
+            assert!(!crate::shuffler::is_valid(&root, v));
+        }
+    }
+
+    #[test]
+    fn missing_required_mode_reaches_nested_objects() {
+        // hq is required, and so are its five properties
+        let root = crate::digger::load_nested();
+        let p = crate::shuffler::payloads(
+            &root,
+            mode(crate::shuffler::Mode::MissingRequired),
+            usize::MAX,
+        )
+        .collect::<Vec<_>>();
+
+        assert_eq!(p.len(), 6);
+        assert_eq!(p[0], serde_json::json!({}));
+        assert!(
+            p[1..]
+                .iter()
+                .all(|v| v["hq"].as_object().unwrap().len() == 4)
+        );
+    }
+
+    #[test]
+    fn exhaustive_payloads_are_tagged_by_validity() {
+        let root = crate::digger::load_flat_level();
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), usize::MAX)
+            .collect::<Vec<_>>();
+
+        let valid = p
+            .iter()
+            .filter(|v| crate::shuffler::is_valid(&root, v))
+            .count();
+        assert_eq!(valid, 1);
+    }
+
+    #[test]
+    fn subsets_are_lexicographic() {
+        assert_eq!(
+            crate::shuffler::subsets(4, 2),
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        assert_eq!(crate::shuffler::subsets(3, 0), vec![Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn pairwise_covering_array_is_much_smaller_than_the_power_set() {
+        let levels = [2; 20];
+        let rows = crate::shuffler::covering_array(&levels, 2, usize::MAX);
+
+        assert!(covers(&rows, &levels, 2));
+        assert!(rows.len() <= 12, "{} rows", rows.len());
+    }
+
+    #[test]
+    fn t_way_covering_array_handles_mixed_levels() {
+        let levels = [3, 2, 4, 2, 3];
+        let rows = crate::shuffler::covering_array(&levels, 3, usize::MAX);
+
+        assert!(covers(&rows, &levels, 3));
+        assert!(rows.len() < levels.iter().product::<usize>());
+    }
+
+    #[test]
+    fn strength_at_least_the_factor_count_is_the_full_product() {
+        let rows = crate::shuffler::covering_array(&[2, 2, 2], 5, usize::MAX);
+        assert_eq!(rows.len(), 8);
+    }
+
+    #[test]
+    fn covering_array_stops_at_max_rows() {
+        let rows = crate::shuffler::covering_array(&[2; 20], 2, 3);
+        assert_eq!(rows.len(), 3);
+    }
+
+    #[test]
+    fn exploding_strength_is_lowered() {
+        assert_eq!(crate::shuffler::tuple_count(&[2, 3, 4], 2), 6 + 8 + 12);
+        assert_eq!(crate::shuffler::tuple_count(&[2; 200], 100), usize::MAX);
+
+        let levels = [2; 200];
+        let rows = crate::shuffler::covering_array(&levels, 100, 50);
+        assert!(!rows.is_empty() && rows.len() <= 50);
+    }
+
+    #[test]
+    fn pairwise_payloads_cover_every_pair_of_properties() {
+        let leaves: Vec<(String, serde_json::Value)> = (0..20)
+            .map(|i| (format!("p{i}"), serde_json::json!(i)))
+            .collect();
+        let leaves: Vec<(&str, serde_json::Value)> = leaves
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.clone()))
+            .collect();
+        let root = root_with_leaves(&leaves);
+        root.borrow_mut().kind = crate::digger::Kind::Object;
+
+        let p = crate::shuffler::payloads(&root, pairwise(), usize::MAX).collect::<Vec<_>>();
+        // a full power set would be over a million payloads
+        assert!(p.len() <= 13, "{} payloads", p.len());
+
+        // every pair of properties is seen together, apart, and each alone
+        for a in 0..20 {
+            for b in (a + 1)..20 {
+                for (has_a, has_b) in [(true, true), (true, false), (false, true), (false, false)] {
+                    assert!(p.iter().any(|v| {
+                        let v = v.as_object().unwrap();
+                        v.contains_key(&format!("p{a}")) == has_a
+                            && v.contains_key(&format!("p{b}")) == has_b
+                    }));
+                }
+            }
+        }
+    }
+
+    fn many_leaves(n: usize) -> std::rc::Rc<std::cell::RefCell<crate::digger::Node>> {
+        let leaves: Vec<(String, serde_json::Value)> = (0..n)
+            .map(|i| (format!("p{i}"), serde_json::json!(i)))
+            .collect();
+        let leaves: Vec<(&str, serde_json::Value)> = leaves
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.clone()))
+            .collect();
+        let root = root_with_leaves(&leaves);
+        root.borrow_mut().kind = crate::digger::Kind::Object;
+        root
+    }
+
+    #[test]
+    fn capped_payloads_stop_without_building_the_power_set() {
+        // 2^22 subsets would take minutes to build upfront
+        let root = many_leaves(22);
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), 3)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            p,
+            [
+                serde_json::json!({"p0": 0}),
+                serde_json::json!({"p1": 1}),
+                serde_json::json!({"p0": 0, "p1": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn more_properties_than_mask_bits_do_not_overflow() {
+        let root = many_leaves(70);
+        let p = crate::shuffler::payloads(&root, mode(crate::shuffler::Mode::Valid), 2)
+            .collect::<Vec<_>>();
+
+        // nothing is required, so valid mode starts from the empty object
+        assert_eq!(p, [serde_json::json!({}), serde_json::json!({"p0": 0})]);
+    }
+
+    #[test]
+    fn pairwise_keeps_required_properties_in_valid_mode() {
+        let root = crate::digger::load_nested_2();
+        let opts = crate::shuffler::Options {
+            mode: crate::shuffler::Mode::Valid,
+            coverage: crate::shuffler::Coverage::TWay(2),
+        };
+
+        let p = crate::shuffler::payloads(&root, opts, usize::MAX).collect::<Vec<_>>();
+        assert!(!p.is_empty());
+        assert!(p.iter().all(|v| crate::shuffler::is_valid(&root, v)));
+    }
+
+    #[test]
+    fn object_payloads_end_with_the_empty_object() {
+        let root = crate::digger::load_flat_level();
+        let p = crate::shuffler::payloads(&root, crate::shuffler::Options::default(), usize::MAX)
+            .collect::<Vec<_>>();
+
+        assert_eq!(p.len(), 8);
+        assert_eq!(p.last().unwrap(), &serde_json::json!({}));
+    }
+
     #[test]
     fn nested_object_combinations_include_all_variants() {
         let root = crate::digger::load_nested();
@@ -373,18 +1240,16 @@ mod tests {
         assert_eq!(c.len(), 32);
 
         // First combination should have hq as null
-        let first = c.get(0).unwrap();
+        let first = c.first().unwrap();
         assert!(first.contains_key("hq"));
         assert_eq!(first.get("hq").unwrap(), &serde_json::Value::Null);
 
         // Other combinations should have hq as an object with various properties
         let has_hq_with_address = c.iter().any(|combo| {
-            if let Some(hq) = combo.get("hq") {
-                if let Some(obj) = hq.as_object() {
-                    return obj.contains_key("address");
-                }
-            }
-            false
+            combo
+                .get("hq")
+                .and_then(serde_json::Value::as_object)
+                .is_some_and(|obj| obj.contains_key("address"))
         });
         assert!(has_hq_with_address);
     }
@@ -0,0 +1,275 @@
+use oas3::Spec;
+use oas3::spec::{ObjectSchema, SchemaType as Type, SchemaTypeSet};
+
+/// Derives a plausible value for a schema that carries no `example`, so every
+/// property can become a leaf. `const`, `default` and the first `enum` entry
+/// are used as-is; otherwise the value is built from `type` and `format`,
+/// kept within `minimum`/`maximum`, `minLength`/`maxLength` and `minItems`.
+pub fn synthesize(schema: &ObjectSchema, spec: &Spec) -> Option<serde_json::Value> {
+    synthesize_at(schema, spec, 0)
+}
+
+fn synthesize_at(schema: &ObjectSchema, spec: &Spec, depth: usize) -> Option<serde_json::Value> {
+    if depth > crate::MAX_DEPTH {
+        return None;
+    }
+
+    if let Some(v) = schema
+        .const_value
+        .as_ref()
+        .or(schema.default.as_ref())
+        .or_else(|| schema.enum_values.first())
+    {
+        return Some(v.clone());
+    }
+
+    // A composition is synthesized as its first alternative.
+    if let Some((_, alt)) = crate::collector::alternatives(schema, spec)
+        .ok()
+        .and_then(|alts| alts.into_iter().next())
+    {
+        return synthesize_at(&alt, spec, depth + 1);
+    }
+
+    match schema_type(schema) {
+        Type::Boolean => Some(serde_json::Value::Bool(true)),
+        Type::Integer => Some(serde_json::json!(integer(schema))),
+        Type::Number => serde_json::Number::from_f64(number(schema)).map(serde_json::Value::Number),
+        Type::String => Some(serde_json::Value::String(string(schema))),
+        Type::Array => {
+            let item = schema
+                .items
+                .as_ref()
+                .and_then(|i| crate::collector::resolve_object_schema(i, spec).ok())
+                .and_then(|i| synthesize_at(&i, spec, depth + 1))?;
+            let len = schema.min_items.unwrap_or(1).max(1);
+            let len = schema.max_items.map_or(len, |max| len.min(max));
+            // A huge `minItems` is not worth a huge payload.
+            let len = len.min(crate::shuffler::MAX_BOUNDARY_ITEMS);
+            let len = usize::try_from(len).unwrap_or(1);
+            Some(serde_json::Value::Array(vec![item; len]))
+        }
+        Type::Object => {
+            let mut obj = serde_json::Map::new();
+            for (name, prop) in &schema.properties {
+                let Ok(prop) = crate::collector::resolve_object_schema(prop, spec) else {
+                    continue;
+                };
+                let value = prop
+                    .example
+                    .clone()
+                    .or_else(|| synthesize_at(&prop, spec, depth + 1));
+                if let Some(v) = value {
+                    obj.insert(name.clone(), v);
+                }
+            }
+            Some(serde_json::Value::Object(obj))
+        }
+        Type::Null => Some(serde_json::Value::Null),
+    }
+}
+
+/// Picks the type to synthesize: the first non-null declared type, or a guess
+/// from the other keywords when `type` is missing.
+fn schema_type(schema: &ObjectSchema) -> Type {
+    match &schema.schema_type {
+        Some(SchemaTypeSet::Single(t)) => *t,
+        Some(SchemaTypeSet::Multiple(types)) => types
+            .iter()
+            .copied()
+            .find(|t| *t != Type::Null)
+            .unwrap_or(Type::Null),
+        None if !schema.properties.is_empty() => Type::Object,
+        None if schema.items.is_some() => Type::Array,
+        None if schema.minimum.is_some() || schema.maximum.is_some() => Type::Number,
+        None => Type::String,
+    }
+}
+
+fn integer(schema: &ObjectSchema) -> i64 {
+    let min = schema
+        .exclusive_minimum
+        .as_ref()
+        .and_then(serde_json::Number::as_f64)
+        .map(|m| m.floor() + 1.0)
+        .or_else(|| {
+            schema
+                .minimum
+                .as_ref()
+                .and_then(serde_json::Number::as_f64)
+                .map(f64::ceil)
+        });
+    let max = schema
+        .exclusive_maximum
+        .as_ref()
+        .and_then(serde_json::Number::as_f64)
+        .map(|m| m.ceil() - 1.0)
+        .or_else(|| {
+            schema
+                .maximum
+                .as_ref()
+                .and_then(serde_json::Number::as_f64)
+                .map(f64::floor)
+        });
+
+    #[allow(clippy::cast_possible_truncation)]
+    let v = match (min, max) {
+        (Some(min), _) => min as i64,
+        (None, Some(max)) if max < 1.0 => max as i64,
+        _ => 1,
+    };
+
+    // Round up to the next multiple, which keeps the value above the minimum.
+    match schema
+        .multiple_of
+        .as_ref()
+        .and_then(serde_json::Number::as_i64)
+    {
+        Some(step) if step > 0 && v % step != 0 => v + (step - v.rem_euclid(step)),
+        _ => v,
+    }
+}
+
+fn number(schema: &ObjectSchema) -> f64 {
+    let as_f64 = |n: Option<&serde_json::Number>| n.and_then(serde_json::Number::as_f64);
+
+    if let Some(min) = as_f64(schema.exclusive_minimum.as_ref()) {
+        return min + 0.5;
+    }
+    if let Some(min) = as_f64(schema.minimum.as_ref()) {
+        return min;
+    }
+    if let Some(max) = as_f64(schema.exclusive_maximum.as_ref()) {
+        return (max - 0.5).min(1.5);
+    }
+    as_f64(schema.maximum.as_ref()).map_or(1.5, |max| max.min(1.5))
+}
+
+fn string(schema: &ObjectSchema) -> String {
+    let base = match schema.format.as_deref() {
+        Some("email" | "idn-email") => "user@example.com",
+        Some("uuid") => "3fa85f64-5717-4562-b3fc-2c963f66afa6",
+        Some("date-time") => "2024-01-01T00:00:00Z",
+        Some("date") => "2024-01-01",
+        Some("time") => "00:00:00Z",
+        Some("uri" | "url" | "iri") => "https://example.com",
+        Some("hostname" | "idn-hostname") => "example.com",
+        Some("ipv4") => "192.0.2.1",
+        Some("ipv6") => "2001:db8::1",
+        Some("password") => "Passw0rd!",
+        Some("byte") => "Zml1dG8=",
+        _ => "string",
+    };
+
+    let mut s = base.to_owned();
+    let len = s.chars().count();
+
+    if let Some(min) = schema.min_length.and_then(|m| usize::try_from(m).ok())
+        && len < min
+    {
+        s.push_str(&"x".repeat(min - len));
+    }
+    if let Some(max) = schema.max_length.and_then(|m| usize::try_from(m).ok())
+        && s.chars().count() > max
+    {
+        s = s.chars().take(max).collect();
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(yaml: &str) -> ObjectSchema {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    fn synth(yaml: &str) -> serde_json::Value {
+        let spec = crate::parse_openapi(std::include_str!("./testdata/single_server.yml")).unwrap();
+        synthesize(&schema(yaml), &spec).unwrap()
+    }
+
+    #[test]
+    fn const_default_and_enum_win_over_type() {
+        assert_eq!(synth("type: string\nconst: fixed"), "fixed");
+        assert_eq!(synth("type: integer\ndefault: 7"), 7);
+        assert_eq!(synth("type: string\nenum: [red, green]"), "red");
+    }
+
+    #[test]
+    fn string_formats() {
+        assert_eq!(synth("type: string\nformat: email"), "user@example.com");
+        assert_eq!(
+            synth("type: string\nformat: uuid"),
+            "3fa85f64-5717-4562-b3fc-2c963f66afa6"
+        );
+        assert_eq!(
+            synth("type: string\nformat: date-time"),
+            "2024-01-01T00:00:00Z"
+        );
+        assert_eq!(synth("type: string\nformat: uri"), "https://example.com");
+        assert_eq!(synth("type: string\nformat: ipv4"), "192.0.2.1");
+        assert_eq!(synth("type: string"), "string");
+    }
+
+    #[test]
+    fn string_length_bounds() {
+        assert_eq!(synth("type: string\nminLength: 10"), "stringxxxx");
+        assert_eq!(synth("type: string\nmaxLength: 3"), "str");
+    }
+
+    #[test]
+    fn integer_bounds() {
+        assert_eq!(synth("type: integer"), 1);
+        assert_eq!(synth("type: integer\nminimum: 18"), 18);
+        assert_eq!(synth("type: integer\nexclusiveMinimum: 18"), 19);
+        assert_eq!(synth("type: integer\nmaximum: -5"), -5);
+        assert_eq!(synth("type: integer\nminimum: 3\nmultipleOf: 5"), 5);
+    }
+
+    #[test]
+    fn number_bounds() {
+        assert_eq!(synth("type: number"), 1.5);
+        assert_eq!(synth("type: number\nminimum: 10"), 10.0);
+        assert_eq!(synth("type: number\nmaximum: 0"), 0.0);
+    }
+
+    #[test]
+    fn boolean_and_nullable_types() {
+        assert_eq!(synth("type: boolean"), true);
+        assert_eq!(synth("type: [string, \"null\"]"), "string");
+    }
+
+    #[test]
+    fn arrays_honour_min_items() {
+        assert_eq!(
+            synth("type: array\nitems:\n  type: integer"),
+            serde_json::json!([1])
+        );
+        assert_eq!(
+            synth("type: array\nminItems: 2\nitems:\n  type: boolean"),
+            serde_json::json!([true, true])
+        );
+    }
+
+    #[test]
+    fn huge_min_items_are_capped() {
+        let v = synth("type: array\nminItems: 100000000\nitems:\n  type: integer");
+        assert_eq!(
+            v.as_array().unwrap().len(),
+            usize::try_from(crate::shuffler::MAX_BOUNDARY_ITEMS).unwrap()
+        );
+    }
+
+    #[test]
+    fn objects_synthesize_every_property() {
+        assert_eq!(
+            synth(
+                "type: object\nproperties:\n  a:\n    type: integer\n  b:\n    type: string\n    example: given"
+            ),
+            serde_json::json!({"a": 1, "b": "given"})
+        );
+    }
+}
@@ -0,0 +1,511 @@
+use oas3::Spec;
+use oas3::spec::{ObjectSchema, Schema, SchemaType as Type, SchemaTypeSet};
+
+/// A place where a value breaks its schema.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Violation {
+    /// JSON pointer to the offending value, empty for the whole document.
+    pub pointer: String,
+    pub message: String,
+}
+
+/// Checks a JSON value against a schema: types, `required`, `enum`/`const`,
+/// `format`, bounds, nested properties and items, and `oneOf`/`anyOf`
+/// alternatives. Every violation is reported, not just the first one.
+pub fn validate(value: &serde_json::Value, schema: &ObjectSchema, spec: &Spec) -> Vec<Violation> {
+    let mut violations = vec![];
+    check(value, schema, "", spec, 0, &mut violations);
+    violations
+}
+
+fn check(
+    value: &serde_json::Value,
+    schema: &ObjectSchema,
+    pointer: &str,
+    spec: &Spec,
+    depth: usize,
+    out: &mut Vec<Violation>,
+) {
+    if depth > crate::MAX_DEPTH {
+        return;
+    }
+
+    let mut report = |message: String| {
+        out.push(Violation {
+            pointer: pointer.to_owned(),
+            message,
+        });
+    };
+
+    match crate::collector::alternatives(schema, spec) {
+        Ok(alternatives) if !alternatives.is_empty() => {
+            let matching = alternatives
+                .iter()
+                .filter(|(_, alt)| validate_at(value, alt, spec, depth).is_empty())
+                .count();
+            if schema.one_of.is_empty() {
+                if matching == 0 {
+                    report("matches none of the anyOf alternatives".to_owned());
+                }
+            } else if matching != 1 {
+                report(format!(
+                    "matches {matching} of the oneOf alternatives instead of exactly one"
+                ));
+            }
+            return;
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Cannot expand the alternatives at {pointer:?}: {e}"),
+    }
+
+    if let Some(types) = &schema.schema_type {
+        let types = match types {
+            SchemaTypeSet::Single(t) => vec![*t],
+            SchemaTypeSet::Multiple(ts) => ts.clone(),
+        };
+        if !types.iter().any(|t| type_matches(*t, value)) {
+            let expected: Vec<&str> = types.iter().copied().map(type_name).collect();
+            report(format!(
+                "expected {}, found {}",
+                expected.join(" or "),
+                value_kind(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(c) = &schema.const_value
+        && c != value
+    {
+        report(format!("expected the constant {c}"));
+    }
+    if !schema.enum_values.is_empty() && !schema.enum_values.contains(value) {
+        report(format!("{value} is not one of the enum values"));
+    }
+
+    match value {
+        serde_json::Value::Number(n) => check_number(n, schema, &mut report),
+        serde_json::Value::String(s) => check_string(s, schema, &mut report),
+        serde_json::Value::Array(items) => {
+            check_array(items, schema, pointer, spec, depth, out);
+        }
+        serde_json::Value::Object(obj) => {
+            check_object(obj, schema, pointer, spec, depth, out);
+        }
+        serde_json::Value::Null | serde_json::Value::Bool(_) => {}
+    }
+}
+
+/// Violations of a schema checked on its own, at the root.
+fn validate_at(
+    value: &serde_json::Value,
+    schema: &ObjectSchema,
+    spec: &Spec,
+    depth: usize,
+) -> Vec<Violation> {
+    let mut violations = vec![];
+    check(value, schema, "", spec, depth + 1, &mut violations);
+    violations
+}
+
+fn check_number(n: &serde_json::Number, schema: &ObjectSchema, report: &mut impl FnMut(String)) {
+    let Some(v) = n.as_f64() else {
+        return;
+    };
+    let bound = |b: Option<&serde_json::Number>| b.and_then(serde_json::Number::as_f64);
+
+    if let Some(min) = bound(schema.minimum.as_ref())
+        && v < min
+    {
+        report(format!("{v} is below the minimum {min}"));
+    }
+    if let Some(min) = bound(schema.exclusive_minimum.as_ref())
+        && v <= min
+    {
+        report(format!("{v} is not above the exclusive minimum {min}"));
+    }
+    if let Some(max) = bound(schema.maximum.as_ref())
+        && v > max
+    {
+        report(format!("{v} is above the maximum {max}"));
+    }
+    if let Some(max) = bound(schema.exclusive_maximum.as_ref())
+        && v >= max
+    {
+        report(format!("{v} is not below the exclusive maximum {max}"));
+    }
+    if let Some(step) = bound(schema.multiple_of.as_ref())
+        && step > 0.0
+        && ((v / step) - (v / step).round()).abs() > 1e-9
+    {
+        report(format!("{v} is not a multiple of {step}"));
+    }
+}
+
+fn check_string(s: &str, schema: &ObjectSchema, report: &mut impl FnMut(String)) {
+    let len = s.chars().count() as u64;
+    if let Some(min) = schema.min_length
+        && len < min
+    {
+        report(format!("length {len} is below minLength {min}"));
+    }
+    if let Some(max) = schema.max_length
+        && len > max
+    {
+        report(format!("length {len} is above maxLength {max}"));
+    }
+
+    if let Some(pattern) = &schema.pattern {
+        match regex::Regex::new(pattern) {
+            Ok(re) if !re.is_match(s) => report(format!("does not match the pattern {pattern}")),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Cannot compile pattern {pattern}: {e}"),
+        }
+    }
+
+    if let Some(format) = schema.format.as_deref()
+        && !format_matches(format, s)
+    {
+        report(format!("{s:?} is not a valid {format}"));
+    }
+}
+
+fn check_array(
+    items: &[serde_json::Value],
+    schema: &ObjectSchema,
+    pointer: &str,
+    spec: &Spec,
+    depth: usize,
+    out: &mut Vec<Violation>,
+) {
+    let len = items.len() as u64;
+    if let Some(min) = schema.min_items
+        && len < min
+    {
+        out.push(violation(
+            pointer,
+            format!("{len} items, below minItems {min}"),
+        ));
+    }
+    if let Some(max) = schema.max_items
+        && len > max
+    {
+        out.push(violation(
+            pointer,
+            format!("{len} items, above maxItems {max}"),
+        ));
+    }
+    if schema.unique_items == Some(true)
+        && items
+            .iter()
+            .enumerate()
+            .any(|(i, v)| items[..i].contains(v))
+    {
+        out.push(violation(pointer, "items are not unique".to_owned()));
+    }
+
+    let Some(item_schema) = schema
+        .items
+        .as_deref()
+        .and_then(|s| resolve(s, pointer, spec))
+    else {
+        return;
+    };
+    for (i, item) in items.iter().enumerate() {
+        check(
+            item,
+            &item_schema,
+            &format!("{pointer}/{i}"),
+            spec,
+            depth + 1,
+            out,
+        );
+    }
+}
+
+fn check_object(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    schema: &ObjectSchema,
+    pointer: &str,
+    spec: &Spec,
+    depth: usize,
+    out: &mut Vec<Violation>,
+) {
+    for name in &schema.required {
+        if !obj.contains_key(name) {
+            out.push(violation(
+                pointer,
+                format!("missing required property {name:?}"),
+            ));
+        }
+    }
+
+    let len = obj.len() as u64;
+    if let Some(min) = schema.min_properties
+        && len < min
+    {
+        out.push(violation(
+            pointer,
+            format!("{len} properties, below minProperties {min}"),
+        ));
+    }
+    if let Some(max) = schema.max_properties
+        && len > max
+    {
+        out.push(violation(
+            pointer,
+            format!("{len} properties, above maxProperties {max}"),
+        ));
+    }
+
+    for (name, v) in obj {
+        let child = format!("{pointer}/{}", escape(name));
+
+        let prop = match (schema.properties.get(name), &schema.additional_properties) {
+            (Some(prop), _) => prop,
+            (None, Some(Schema::Boolean(b))) if !b.0 => {
+                out.push(violation(
+                    &child,
+                    "property not allowed by additionalProperties".to_owned(),
+                ));
+                continue;
+            }
+            (None, Some(additional @ Schema::Object(_))) => additional,
+            (None, _) => continue,
+        };
+
+        if let Some(prop) = resolve(prop, &child, spec) {
+            check(v, &prop, &child, spec, depth + 1, out);
+        }
+    }
+}
+
+fn resolve(schema: &Schema, pointer: &str, spec: &Spec) -> Option<ObjectSchema> {
+    crate::collector::resolve_object_schema(schema, spec)
+        .inspect_err(|e| tracing::warn!("Cannot resolve the schema at {pointer:?}: {e}"))
+        .ok()
+}
+
+fn violation(pointer: &str, message: String) -> Violation {
+    Violation {
+        pointer: pointer.to_owned(),
+        message,
+    }
+}
+
+/// Escapes a property name as a JSON pointer reference token (RFC 6901).
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+fn type_matches(t: Type, value: &serde_json::Value) -> bool {
+    match t {
+        Type::Boolean => value.is_boolean(),
+        Type::Integer => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        Type::Number => value.is_number(),
+        Type::String => value.is_string(),
+        Type::Array => value.is_array(),
+        Type::Object => value.is_object(),
+        Type::Null => value.is_null(),
+    }
+}
+
+const fn type_name(t: Type) -> &'static str {
+    match t {
+        Type::Boolean => "boolean",
+        Type::Integer => "integer",
+        Type::Number => "number",
+        Type::String => "string",
+        Type::Array => "array",
+        Type::Object => "object",
+        Type::Null => "null",
+    }
+}
+
+const fn value_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Checks the formats fiuto knows about; any other format is accepted.
+fn format_matches(format: &str, s: &str) -> bool {
+    static DATE_TIME: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(
+            r"^\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$",
+        )
+        .expect("valid regex")
+    });
+    static DATE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"^\d{4}-\d{2}-\d{2}$").expect("valid regex")
+    });
+    static UUID: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"^[0-9a-fA-F]{8}-([0-9a-fA-F]{4}-){3}[0-9a-fA-F]{12}$")
+            .expect("valid regex")
+    });
+    static URI: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:\S+$").expect("valid regex")
+    });
+
+    match format {
+        "date-time" => DATE_TIME.is_match(s),
+        "date" => DATE.is_match(s),
+        "uuid" => UUID.is_match(s),
+        "uri" | "url" => URI.is_match(s),
+        "email" | "idn-email" => s
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.')),
+        "ipv4" => s.parse::<std::net::Ipv4Addr>().is_ok(),
+        "ipv6" => s.parse::<std::net::Ipv6Addr>().is_ok(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> Spec {
+        crate::parse_openapi(std::include_str!("./testdata/get_profile_response.yml")).unwrap()
+    }
+
+    fn schema(yaml: &str) -> ObjectSchema {
+        serde_yaml_bw::from_str(yaml).unwrap()
+    }
+
+    fn pointers(value: &serde_json::Value, yaml: &str) -> Vec<String> {
+        validate(value, &schema(yaml), &spec())
+            .into_iter()
+            .map(|v| v.pointer)
+            .collect()
+    }
+
+    #[test]
+    fn matching_values_have_no_violations() {
+        let v = serde_json::json!({"id": 1, "name": "fiuto", "tags": ["a"]});
+        let yaml = "type: object\nrequired: [id, name]\nproperties:\n  id:\n    type: integer\n  name:\n    type: string\n  tags:\n    type: array\n    items:\n      type: string";
+        assert!(pointers(&v, yaml).is_empty());
+    }
+
+    #[test]
+    fn type_mismatches_point_at_the_value() {
+        let v = serde_json::json!({"id": "1", "tags": ["a", 2]});
+        let yaml = "type: object\nproperties:\n  id:\n    type: integer\n  tags:\n    type: array\n    items:\n      type: string";
+        assert_eq!(pointers(&v, yaml), ["/id", "/tags/1"]);
+    }
+
+    #[test]
+    fn missing_required_properties_point_at_the_object() {
+        let violations = validate(
+            &serde_json::json!({"a": {}}),
+            &schema("type: object\nproperties:\n  a:\n    type: object\n    required: [b]"),
+            &spec(),
+        );
+        assert_eq!(
+            violations,
+            [Violation {
+                pointer: "/a".to_owned(),
+                message: "missing required property \"b\"".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn enums_formats_and_bounds() {
+        assert_eq!(
+            pointers(
+                &serde_json::json!("blue"),
+                "type: string\nenum: [red, green]"
+            ),
+            [""]
+        );
+        assert_eq!(
+            pointers(&serde_json::json!("nope"), "type: string\nformat: email"),
+            [""]
+        );
+        assert!(pointers(&serde_json::json!("a@b.io"), "type: string\nformat: email").is_empty());
+        assert_eq!(
+            pointers(
+                &serde_json::json!("2024-13"),
+                "type: string\nformat: date-time"
+            ),
+            [""]
+        );
+        assert_eq!(
+            pointers(&serde_json::json!(5), "type: integer\nminimum: 10"),
+            [""]
+        );
+        assert_eq!(
+            pointers(
+                &serde_json::json!("ab"),
+                "type: string\nminLength: 3\npattern: '^x'"
+            ),
+            ["", ""]
+        );
+    }
+
+    #[test]
+    fn nullable_types_accept_null() {
+        assert!(pointers(&serde_json::Value::Null, "type: [string, \"null\"]").is_empty());
+    }
+
+    #[test]
+    fn additional_properties_false_rejects_extra_keys() {
+        assert_eq!(
+            pointers(
+                &serde_json::json!({"a": 1, "b/c": 2}),
+                "type: object\nadditionalProperties: false\nproperties:\n  a:\n    type: integer"
+            ),
+            ["/b~1c"]
+        );
+    }
+
+    #[test]
+    fn nested_refs_are_followed() {
+        let spec = spec();
+        let profile = spec.components.as_ref().unwrap().schemas["Profile"].clone();
+        let profile = crate::collector::resolve_object_schema(&profile, &spec).unwrap();
+
+        let v = serde_json::json!({"id": 1, "name": "x", "address": {"city": 5}});
+        let violations = validate(&v, &profile, &spec);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].pointer, "/address/city");
+    }
+
+    #[test]
+    fn one_of_needs_exactly_one_match() {
+        let yaml = "oneOf:\n  - type: integer\n  - type: number";
+        // an integer is also a number
+        assert_eq!(pointers(&serde_json::json!(1), yaml), [""]);
+        assert!(pointers(&serde_json::json!(1.5), yaml).is_empty());
+        assert!(
+            pointers(
+                &serde_json::json!(1),
+                "anyOf:\n  - type: integer\n  - type: number"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn nullable_from_30_accepts_null() {
+        let spec = crate::parse_openapi(
+            "openapi: 3.0.3\ninfo:\n  title: t\n  version: '1.0'\npaths: {}\ncomponents:\n  schemas:\n    User:\n      type: object\n      properties:\n        nick:\n          type: string\n          nullable: true",
+        )
+        .unwrap();
+        let schemas = &spec.components.as_ref().unwrap().schemas;
+        let user = crate::collector::resolve_object_schema(&schemas["User"], &spec).unwrap();
+
+        assert!(validate(&serde_json::json!({"nick": null}), &user, &spec).is_empty());
+        let wrong = validate(&serde_json::json!({"nick": 1}), &user, &spec);
+        assert_eq!(wrong.len(), 1);
+        assert_eq!(wrong[0].pointer, "/nick");
+    }
+}
@@ -12,7 +12,32 @@ async fn run_api() -> String {
         .route("/api/v1/login", axum::routing::post(login_handler))
         .route("/api/v1/org/info", axum::routing::post(post_info))
         .route("/api/v1/org/hq", axum::routing::post(post_hq))
-        .route("/api/v1/org/settings", axum::routing::put(put_settings))
+        .route(
+            "/api/v1/org/settings",
+            axum::routing::put(put_settings).patch(patch_settings),
+        )
+        .route("/api/v1/org/members", axum::routing::delete(delete_members))
+        .route("/api/v1/org/info", axum::routing::delete(delete_info))
+        .route(
+            "/api/v1/orgs/{org}/users/{id}",
+            axum::routing::get(get_user),
+        )
+        .route("/api/v1/users", axum::routing::get(list_users))
+        .route("/api/v1/tenant", axum::routing::get(get_tenant))
+        .route("/api/v1/org/members/bulk", axum::routing::post(add_members))
+        .route("/api/v1/pets", axum::routing::post(add_pet))
+        .route("/api/v1/profile", axum::routing::get(get_profile))
+        .route("/api/v1/profile/avatar", axum::routing::get(get_avatar))
+        .route("/api/v1/keys/header", axum::routing::get(key_in_header))
+        .route("/api/v1/keys/query", axum::routing::get(key_in_query))
+        .route(
+            "/api/v1/keys/both",
+            axum::routing::get(keys_in_header_and_cookie),
+        )
+        .route("/api/v1/keys/either", axum::routing::get(key_in_query))
+        .route("/api/v1/auth/basic", axum::routing::get(basic_login))
+        .route("/api/v1/auth/digest", axum::routing::get(digest_login))
+        .route("/api/v1/auth/token", axum::routing::get(token_login))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:0").await.unwrap();
@@ -27,7 +52,7 @@ async fn run_api() -> String {
     base_url
 }
 
-/// LoginRequest is the body expected for a simple login request.
+/// `LoginRequest` is the body expected for a simple login request.
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
 struct LoginRequest {
     email: String,
@@ -85,7 +110,7 @@ where
 
         tracing::info!("header bearer: {:?}", bearer);
 
-        let token_data = Claims {
+        let token_data = Self {
             token_received: bearer.token().to_owned(),
         };
 
@@ -142,16 +167,200 @@ async fn put_settings(axum::Json(payload): axum::Json<InfoRequest>) -> axum::Jso
     axum::Json("ok".to_string())
 }
 
+// Merge patches accept any subset of fields, but only under the merge-patch
+// media type, so a request sent as plain JSON is rejected with 415.
+async fn patch_settings(
+    headers: axum::http::HeaderMap,
+    axum::Json(payload): axum::Json<serde_json::Value>,
+) -> axum::http::StatusCode {
+    tracing::info!("patch settings received: {:?}", payload);
+    let content_type = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    if content_type == Some("application/merge-patch+json") {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct DeleteMembersRequest {
+    email: String,
+}
+
+async fn delete_members(
+    axum::Json(payload): axum::Json<DeleteMembersRequest>,
+) -> axum::Json<String> {
+    tracing::info!("delete members received: {:?}", payload);
+    axum::Json("ok".to_string())
+}
+
+async fn delete_info() -> axum::http::StatusCode {
+    axum::http::StatusCode::NO_CONTENT
+}
+
+async fn get_user(
+    axum::extract::Path((org, id)): axum::extract::Path<(String, u32)>,
+) -> axum::Json<String> {
+    axum::Json(format!("user {id} of {org}"))
+}
+
+// `page` is mandatory, everything else in the query is accepted as-is.
+async fn list_users(
+    axum::extract::RawQuery(query): axum::extract::RawQuery,
+) -> axum::http::StatusCode {
+    let has_page = query
+        .unwrap_or_default()
+        .split('&')
+        .any(|kv| kv.starts_with("page="));
+    if has_page {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::BAD_REQUEST
+    }
+}
+
+// The tenant header selects the data, so without it the request is rejected.
+async fn get_tenant(headers: axum::http::HeaderMap) -> axum::http::StatusCode {
+    if headers.contains_key("x-tenant-id") {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::BAD_REQUEST
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
+struct Member {
+    email: String,
+    role: String,
+}
+
+async fn add_members(axum::Json(payload): axum::Json<Vec<Member>>) -> axum::Json<usize> {
+    axum::Json(payload.len())
+}
+
 // this return the token populated during the request, this way we can use it for test checks.
 async fn more_info(claims: Claims) -> axum::Json<String> {
     axum::Json(claims.token_received)
 }
 
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "petType", rename_all = "lowercase", deny_unknown_fields)]
+enum Pet {
+    Cat { name: String, indoor: bool },
+    Dog { name: String, bark: String },
+}
+
+async fn add_pet(axum::Json(payload): axum::Json<Pet>) -> axum::Json<String> {
+    tracing::info!("Received pet: {:?}", payload);
+    match payload {
+        Pet::Cat { name, indoor } => axum::Json(format!("{name} indoor: {indoor}")),
+        Pet::Dog { name, bark } => axum::Json(format!("{name} says {bark}")),
+    }
+}
+
+/// Drifts from the documented `Profile`: `id` is a string and `name` is
+/// missing.
+async fn get_profile() -> axum::Json<serde_json::Value> {
+    axum::Json(serde_json::json!({
+        "id": "42",
+        "tags": ["admin", 7],
+        "address": {"city": "Verona"}
+    }))
+}
+
+/// Answers with text where the spec documents a PNG.
+async fn get_avatar() -> &'static str {
+    "no avatar yet"
+}
+
+const fn status_if(authorized: bool) -> axum::http::StatusCode {
+    if authorized {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::UNAUTHORIZED
+    }
+}
+
+async fn key_in_header(headers: axum::http::HeaderMap) -> axum::http::StatusCode {
+    status_if(headers.get("x-api-key").is_some_and(|v| v == "k-header"))
+}
+
+async fn key_in_query(
+    axum::extract::RawQuery(query): axum::extract::RawQuery,
+) -> axum::http::StatusCode {
+    status_if(query.is_some_and(|q| q.split('&').any(|p| p == "api_key=k-query")))
+}
+
+fn authorization(headers: &axum::http::HeaderMap) -> &str {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
+
+// "user:pass" in base64
+async fn basic_login(headers: axum::http::HeaderMap) -> axum::http::StatusCode {
+    status_if(authorization(&headers) == "Basic dXNlcjpwYXNz")
+}
+
+async fn token_login(headers: axum::http::HeaderMap) -> axum::http::StatusCode {
+    status_if(authorization(&headers) == "Token t0k3n")
+}
+
+// Challenges until the answer echoes the challenge; the hash itself is
+// checked against the RFC examples in the unit tests.
+async fn digest_login(headers: axum::http::HeaderMap) -> axum::response::Response {
+    let answer = authorization(&headers);
+    let answered = answer.starts_with("Digest ")
+        && [
+            r#"username="user""#,
+            r#"realm="fiuto""#,
+            r#"nonce="n0nc3""#,
+            r#"uri="/api/v1/auth/digest""#,
+            r#"opaque="0p4qu3""#,
+            "qop=auth",
+        ]
+        .iter()
+        .all(|part| answer.contains(part));
+
+    if answered {
+        return axum::response::IntoResponse::into_response(axum::http::StatusCode::OK);
+    }
+    axum::response::IntoResponse::into_response((
+        axum::http::StatusCode::UNAUTHORIZED,
+        [(
+            axum::http::header::WWW_AUTHENTICATE,
+            r#"Digest realm="fiuto", qop="auth", nonce="n0nc3", opaque="0p4qu3""#,
+        )],
+    ))
+}
+
+// Both keys are needed together, next to the `theme` cookie parameter.
+async fn keys_in_header_and_cookie(headers: axum::http::HeaderMap) -> axum::http::StatusCode {
+    let cookie = headers
+        .get(axum::http::header::COOKIE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let has_key = headers.get("x-api-key").is_some_and(|v| v == "k-header");
+    status_if(has_key && cookie == "theme=dark; session_id=k-cookie")
+}
+
 /// This is a fake test to make sure the test suite is setup with tracing.
 #[test]
 fn fake_test() {
     tracing_subscriber::fmt::init();
-    assert!(true);
+}
+
+/// Planning and sending lazily must not keep the run from being spawned.
+#[test]
+fn run_can_be_spawned() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    assert_send(&fiuto::Driller::new(openapi_schema).run());
 }
 
 #[tokio::test]
@@ -166,7 +375,7 @@ async fn get_info_simple() {
         .await;
 
     assert!(r.is_ok());
-    let r = r.unwrap();
+    let r = r.unwrap().results;
 
     assert_eq!(r.len(), 1);
 
@@ -188,7 +397,7 @@ async fn post_login() {
         .await;
 
     assert!(r.is_ok());
-    let r = r.unwrap();
+    let r = r.unwrap().results;
 
     assert_eq!(r.len(), 1); // 1 endpoint
 
@@ -202,6 +411,136 @@ async fn post_login() {
 
     assert_eq!(success_count, 1, "Only complete payload should succeed");
     assert_eq!(error_count, 7, "Incomplete payloads should return 422");
+
+    // the expectation matches what the server did
+    for c in combinations {
+        let expected = if c.status_code == 200 {
+            fiuto::Expectation::Valid
+        } else {
+            fiuto::Expectation::Invalid
+        };
+        assert_eq!(c.expected, expected, "payload: {}", c.payload);
+        // both 200 and 422 are documented
+        assert!(!c.undocumented_status);
+        // the handler answers with a plain string instead of `LoginResponse`
+        assert_eq!(c.body_violations.is_empty(), c.status_code != 200);
+    }
+}
+
+#[tokio::test]
+async fn post_login_valid_mode_sends_only_complete_payload() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .mode(fiuto::Mode::Valid)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // every property is required, so there is a single valid payload
+    let calls = r.first().unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].status_code, 200);
+    assert_eq!(calls[0].expected, fiuto::Expectation::Valid);
+}
+
+#[tokio::test]
+async fn post_login_pairwise_covers_every_pair() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .coverage(fiuto::Coverage::TWay(2))
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let calls = r.first().unwrap();
+    // fewer than the 2^3 - 1 combinations plus the empty payload
+    assert!(calls.len() < 8, "{} calls", calls.len());
+
+    let fields = ["email", "org", "password"];
+    for (i, a) in fields.iter().enumerate() {
+        for b in &fields[i + 1..] {
+            for (has_a, has_b) in [(true, true), (true, false), (false, true), (false, false)] {
+                assert!(calls.iter().any(|c| {
+                    let v: serde_json::Value = serde_json::from_str(&c.payload).unwrap();
+                    v.get(a).is_some() == has_a && v.get(b).is_some() == has_b
+                }));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn max_requests_caps_each_operation() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .max_requests_per_operation(3)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.first().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn max_requests_stops_generating_payloads() {
+    let url = run_api().await;
+
+    // the nested object alone has 2^22 combinations
+    let s = std::include_str!("../src/testdata/post_many_optional.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = tokio::time::timeout(
+        std::time::Duration::from_secs(10),
+        fiuto::Driller::new(openapi_schema)
+            .base_url(url)
+            .max_requests_per_operation(3)
+            .run(),
+    )
+    .await
+    .expect("payload generation should stop at the cap")
+    .unwrap()
+    .results;
+
+    assert_eq!(r.first().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn post_login_missing_required_mode_is_rejected() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .mode(fiuto::Mode::MissingRequired)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // one payload per required property left out
+    let calls = r.first().unwrap();
+    assert_eq!(calls.len(), 3);
+    assert!(calls.iter().all(|c| c.status_code == 422));
+    assert!(
+        calls
+            .iter()
+            .all(|c| c.expected == fiuto::Expectation::Invalid)
+    );
 }
 
 #[tokio::test]
@@ -219,7 +558,8 @@ async fn post_login_openapi_31_drills_end_to_end() {
         .base_url(url)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     assert_eq!(r.len(), 1);
 
@@ -253,7 +593,7 @@ async fn get_with_jwt() {
 
     assert!(r.is_ok());
 
-    let r = r.unwrap();
+    let r = r.unwrap().results;
     assert_eq!(r.len(), 1);
 
     let endpoint_results = r.first().unwrap();
@@ -276,7 +616,7 @@ async fn post_with_jwt() {
 
     assert!(r.is_ok());
 
-    let r = r.unwrap();
+    let r = r.unwrap().results;
     assert_eq!(r.len(), 1);
 
     let combinations = r.first().unwrap();
@@ -286,6 +626,14 @@ async fn post_with_jwt() {
     // with valid JWT: complete payload should succeed, empty should fail
     let success_count = combinations.iter().filter(|c| c.status_code == 200).count();
     assert_eq!(success_count, 1, "Complete payload with JWT should succeed");
+
+    // the token is sent but never written to the results
+    for c in combinations {
+        assert_eq!(c.method, "POST");
+        assert_eq!(c.operation_id.as_deref(), Some("postInfo"));
+        assert_eq!(c.request_headers["authorization"], "<redacted>");
+        assert!(!c.response_body.is_empty());
+    }
 }
 
 #[tokio::test]
@@ -301,7 +649,7 @@ async fn post_with_nested_property_body() {
 
     assert!(r.is_ok());
 
-    let r = r.unwrap();
+    let r = r.unwrap().results;
     assert_eq!(r.len(), 1);
 
     let combinations = r.first().unwrap();
@@ -327,7 +675,7 @@ async fn get_without_jwt_returns_401() {
 
     assert!(r.is_ok());
 
-    let r = r.unwrap();
+    let r = r.unwrap().results;
     assert_eq!(r.len(), 1);
 
     let endpoint_results = r.first().unwrap();
@@ -350,7 +698,7 @@ async fn post_without_jwt_returns_401() {
 
     assert!(r.is_ok());
 
-    let r = r.unwrap();
+    let r = r.unwrap().results;
     assert_eq!(r.len(), 1);
 
     let combinations = r.first().unwrap();
@@ -371,7 +719,8 @@ async fn put_drills_request_body() {
         .base_url(url)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     assert_eq!(r.len(), 1);
 
@@ -396,7 +745,8 @@ async fn multi_endpoint_runs_get_and_post() {
         .base_url(url)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     // one GET endpoint and one POST endpoint
     assert_eq!(r.len(), 2);
@@ -414,24 +764,51 @@ async fn multi_endpoint_runs_get_and_post() {
 }
 
 #[tokio::test]
-async fn post_without_property_examples_sends_only_empty_payload() {
+async fn post_with_only_object_level_example_uses_it() {
     let url = run_api().await;
 
-    // No per-property example means the digger produces no leaves, so the only
-    // request driven is the empty payload that drill_post_endpoint always adds.
+    // No per-property example, so leaf values come from the example given
+    // for the whole object and the payload is drilled like one with examples.
     let s = std::include_str!("../src/testdata/post_login_obj_example.yml");
     let openapi_schema = fiuto::parse_openapi(s).unwrap();
     let r = fiuto::Driller::new(openapi_schema)
         .base_url(url)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     assert_eq!(r.len(), 1);
     let combinations = r.first().unwrap();
-    assert_eq!(combinations.len(), 1, "only the empty payload is sent");
-    // login_handler needs all fields, so the empty payload is rejected
-    assert_eq!(combinations[0].status_code, 422);
+    // 2^3 - 1 combinations + 1 empty payload
+    assert_eq!(combinations.len(), 8);
+    // login_handler needs all fields, so only the complete payload succeeds
+    let success: Vec<_> = combinations
+        .iter()
+        .filter(|c| c.status_code == 200)
+        .collect();
+    assert_eq!(success.len(), 1, "only the complete payload should succeed");
+    assert!(success[0].payload.contains("CiaoCiao1234$#"));
+}
+
+#[tokio::test]
+async fn post_without_any_example_synthesizes_values() {
+    let url = run_api().await;
+
+    // No example at all, so leaf values are synthesized from the schema.
+    let s = std::include_str!("../src/testdata/post_login_no_examples.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let combinations = r.first().unwrap();
+    assert_eq!(combinations.len(), 8);
+    let success = combinations.iter().filter(|c| c.status_code == 200).count();
+    assert_eq!(success, 1, "only the complete payload should succeed");
 }
 
 #[tokio::test]
@@ -444,7 +821,8 @@ async fn deprecated_endpoints_are_skipped_end_to_end() {
         .base_url(url)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     // The only endpoint is deprecated, so nothing is executed.
     assert!(r.is_empty(), "deprecated endpoint should not be called");
@@ -462,7 +840,8 @@ async fn cli_base_url_overrides_spec_server() {
         .base_url(url.clone())
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
 
     let endpoint_results = r.first().unwrap();
     assert!(
@@ -483,6 +862,7 @@ async fn throttle_delays_between_requests() {
     let throttle = fiuto::Throttle {
         delay: std::time::Duration::from_millis(80),
         every: 1,
+        ..fiuto::Throttle::default()
     };
 
     let start = std::time::Instant::now();
@@ -491,7 +871,8 @@ async fn throttle_delays_between_requests() {
         .throttle(throttle)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
     let elapsed = start.elapsed();
 
     let total: usize = r.iter().map(std::vec::Vec::len).sum();
@@ -506,6 +887,40 @@ async fn throttle_delays_between_requests() {
     );
 }
 
+#[tokio::test]
+async fn throttle_holds_across_concurrent_requests() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/multi_endpoint.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+
+    let throttle = fiuto::Throttle {
+        delay: std::time::Duration::from_millis(80),
+        every: 3,
+        ..fiuto::Throttle::default()
+    };
+
+    let start = std::time::Instant::now();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .throttle(throttle)
+        .concurrency(8)
+        .run()
+        .await
+        .unwrap()
+        .results;
+    let elapsed = start.elapsed();
+
+    // 9 requests in groups of 3: the workers still pause twice in total
+    let total: usize = r.iter().map(std::vec::Vec::len).sum();
+    assert_eq!(total, 9);
+    let min = std::time::Duration::from_millis(160);
+    assert!(
+        elapsed >= min,
+        "expected at least {min:?}, took {elapsed:?}"
+    );
+}
+
 #[tokio::test]
 async fn throttle_every_skips_when_group_not_reached() {
     let url = run_api().await;
@@ -517,6 +932,7 @@ async fn throttle_every_skips_when_group_not_reached() {
     let throttle = fiuto::Throttle {
         delay: std::time::Duration::from_secs(5),
         every: 1000,
+        ..fiuto::Throttle::default()
     };
 
     let start = std::time::Instant::now();
@@ -525,7 +941,8 @@ async fn throttle_every_skips_when_group_not_reached() {
         .throttle(throttle)
         .run()
         .await
-        .unwrap();
+        .unwrap()
+        .results;
     let elapsed = start.elapsed();
 
     let total: usize = r.iter().map(std::vec::Vec::len).sum();
@@ -535,3 +952,1170 @@ async fn throttle_every_skips_when_group_not_reached() {
         "no delay expected, took {elapsed:?}"
     );
 }
+
+#[tokio::test]
+async fn patch_drills_merge_patch_body() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/patch_settings.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let combinations = r.first().unwrap();
+    // SettingsPatch has 2 properties: 2^2 - 1 + 1 empty = 4 combinations
+    assert_eq!(combinations.len(), 4);
+
+    // every subset is a valid merge patch, and the handler only answers 200
+    // when the spec's merge-patch media type is sent as Content-Type
+    assert!(
+        combinations.iter().all(|c| c.status_code == 200),
+        "every PATCH payload should be sent as merge-patch+json"
+    );
+}
+
+#[tokio::test]
+async fn delete_drills_with_and_without_body() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/delete_members.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 2);
+
+    // paths come back in spec order, so look each one up by its path
+    let by_path = |suffix: &str| {
+        r.iter()
+            .find(|results| results[0].path.ends_with(suffix))
+            .unwrap()
+    };
+
+    // the bodyless /org/info is fired once
+    let info = by_path("/api/v1/org/info");
+    assert_eq!(info.len(), 1);
+    assert_eq!(info[0].status_code, 204);
+    assert!(info[0].payload.is_empty());
+    assert_eq!(info[0].method, "DELETE");
+    assert_eq!(info[0].operation_id.as_deref(), Some("deleteOrgInfo"));
+    assert_eq!(info[0].variant, "single");
+
+    // /org/members drills its body: 2^1 - 1 + 1 empty = 2 combinations
+    let members = by_path("/api/v1/org/members");
+    assert_eq!(members.len(), 2);
+    let success = members.iter().filter(|c| c.status_code == 200).count();
+    let rejected = members.iter().filter(|c| c.status_code == 422).count();
+    assert_eq!(success, 1, "complete DELETE payload should succeed");
+    assert_eq!(rejected, 1, "empty DELETE payload should be rejected");
+}
+
+#[tokio::test]
+async fn get_expands_path_parameters() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_user_path_params.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let endpoint_results = r.first().unwrap();
+    assert_eq!(endpoint_results.len(), 1);
+    // `org` comes from the path item schema example and gets percent-encoded,
+    // `id` takes the operation-level example over the path-level one
+    assert_eq!(
+        endpoint_results[0].path,
+        format!("{url}/api/v1/orgs/fiuto%20io/users/42")
+    );
+    assert_eq!(endpoint_results[0].status_code, 200);
+}
+
+#[tokio::test]
+async fn get_drills_query_parameter_combinations() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_users_query.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let calls = r.first().unwrap();
+    // 2 optional parameters: 2^2 - 1 subsets + the required-only variant
+    assert_eq!(calls.len(), 4);
+
+    // `page` is required so it is always sent and every call succeeds
+    assert!(calls.iter().all(|c| c.status_code == 200));
+    assert!(calls.iter().all(|c| c.path.contains("page=2")));
+
+    let paths: Vec<&str> = calls.iter().map(|c| c.path.as_str()).collect();
+    // arrays explode by default and `sort` falls back to its first enum value
+    assert!(
+        paths
+            .contains(&format!("{url}/api/v1/users?page=2&tags=admin&tags=ops&sort=name").as_str())
+    );
+    assert!(paths.contains(&format!("{url}/api/v1/users?page=2").as_str()));
+
+    // each call records its own query string and variant
+    assert!(
+        calls
+            .iter()
+            .all(|c| c.method == "GET" && c.query.starts_with("page=2"))
+    );
+    let variants: std::collections::BTreeSet<&str> =
+        calls.iter().map(|c| c.variant.as_str()).collect();
+    assert_eq!(variants.len(), calls.len());
+}
+
+#[tokio::test]
+async fn get_combines_header_and_cookie_parameters() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_tenant_headers.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let calls = r.first().unwrap();
+    // X-Tenant-Id: 2 examples, Accept-Language and the cookie: present or absent
+    assert_eq!(calls.len(), 2 * 2 * 2);
+
+    // the required tenant header is on every call, so every call succeeds
+    assert!(calls.iter().all(|c| c.status_code == 200));
+    let tenants: std::collections::BTreeSet<&str> = calls
+        .iter()
+        .map(|c| c.headers["X-Tenant-Id"].as_str())
+        .collect();
+    assert_eq!(tenants, ["acme", "globex"].into_iter().collect());
+
+    let with_cookie = calls
+        .iter()
+        .filter(|c| c.headers.get("Cookie").map(String::as_str) == Some("session=abc"))
+        .count();
+    assert_eq!(
+        with_cookie, 4,
+        "the optional cookie is sent on half the calls"
+    );
+}
+
+#[tokio::test]
+async fn post_drills_top_level_array_body() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_members_array_body.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let calls = r.first().unwrap();
+    // [full], [], [full, full] and one single-element array per partial member
+    assert_eq!(calls.len(), 5);
+
+    // arrays of complete members (including the empty one) are accepted, the
+    // partial members are rejected
+    let success = calls.iter().filter(|c| c.status_code == 200).count();
+    let rejected = calls.iter().filter(|c| c.status_code == 422).count();
+    assert_eq!(success, 3);
+    assert_eq!(rejected, 2);
+    assert!(calls.iter().any(|c| c.payload == "[]"));
+
+    // only 200 is documented, so the 422 are flagged, in the JSON output too
+    for c in calls {
+        assert_eq!(c.undocumented_status, c.status_code == 422);
+        let json = serde_json::to_value(c).unwrap();
+        assert_eq!(json["undocumented_status"], c.status_code == 422);
+    }
+}
+
+#[tokio::test]
+async fn post_drills_one_of_alternatives() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_pets_one_of.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.len(), 1);
+
+    let calls = r.first().unwrap();
+    // 7 combinations for each of the two alternatives, the shared
+    // `{"name": "Fido"}` only once, plus the empty object
+    assert_eq!(calls.len(), 14);
+
+    // only the complete cat and the complete dog are accepted
+    let success: Vec<_> = calls.iter().filter(|c| c.status_code == 200).collect();
+    assert_eq!(success.len(), 2);
+    assert!(
+        success
+            .iter()
+            .any(|c| c.payload.contains(r#""petType":"cat""#))
+    );
+    assert!(
+        success
+            .iter()
+            .any(|c| c.payload.contains(r#""petType":"dog""#))
+    );
+}
+
+#[tokio::test]
+async fn response_body_drift_is_reported_with_pointers() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_profile_response.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let call = &r[0][0];
+    assert_eq!(call.status_code, 200);
+
+    let pointers: Vec<&str> = call
+        .body_violations
+        .iter()
+        .map(|v| v.pointer.as_str())
+        .collect();
+    assert_eq!(pointers, ["", "/id", "/tags/1"]);
+    assert!(call.body_violations[0].message.contains("\"name\""));
+
+    // the documented header is missing, the content type is right
+    assert_eq!(
+        call.response_mismatches,
+        ["missing required header X-Request-Id"]
+    );
+    assert_eq!(call.response_headers["content-type"], "application/json");
+
+    let avatar = &r[1][0];
+    assert_eq!(avatar.status_code, 200);
+    assert_eq!(
+        avatar.response_mismatches,
+        ["Content-Type text/plain; charset=utf-8 is not documented, expected one of image/png"]
+    );
+}
+
+/// A base URL nothing listens on: the port is taken then released.
+async fn closed_port() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    base_url
+}
+
+/// A server that accepts connections and closes them without answering.
+async fn run_hanging_up() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            drop(socket);
+        }
+    });
+
+    base_url
+}
+
+#[tokio::test]
+async fn refused_connection_is_recorded_and_the_run_goes_on() {
+    let s = std::include_str!("../src/testdata/delete_members.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(closed_port().await)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // both operations are still drilled, every call failing on its own
+    assert_eq!(r.len(), 2);
+    let calls: Vec<&fiuto::CallResult> = r.iter().flatten().collect();
+    assert_eq!(calls.len(), 3);
+    for c in calls {
+        assert_eq!(c.outcome, fiuto::Outcome::Connect);
+        assert_eq!(c.status_code, 0);
+        assert!(c.error.is_some());
+        assert!(!c.undocumented_status);
+    }
+}
+
+#[tokio::test]
+async fn connection_closed_without_response_is_a_reset() {
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(run_hanging_up().await)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let call = &r[0][0];
+    assert_eq!(call.outcome, fiuto::Outcome::Reset);
+    assert!(call.response_body.is_empty());
+}
+
+#[tokio::test]
+async fn fail_fast_aborts_on_the_first_transport_failure() {
+    let s = std::include_str!("../src/testdata/delete_members.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(closed_port().await)
+        .fail_fast(true)
+        .run()
+        .await;
+
+    assert!(r.is_err());
+}
+
+/// A server answering every request after 100ms.
+async fn run_slow_api() -> String {
+    let app = axum::Router::new().route(
+        "/api/v1/users",
+        axum::routing::get(|| async {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            "ok"
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    base_url
+}
+
+#[tokio::test]
+async fn concurrent_calls_overlap() {
+    // 4 query variants of 100ms each: about 400ms one at a time
+    let s = std::include_str!("../src/testdata/get_users_query.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+
+    let start = std::time::Instant::now();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(run_slow_api().await)
+        .concurrency(4)
+        .run()
+        .await
+        .unwrap()
+        .results;
+    let elapsed = start.elapsed();
+
+    assert_eq!(r[0].len(), 4);
+    assert!(
+        elapsed < std::time::Duration::from_millis(300),
+        "calls did not overlap, took {elapsed:?}"
+    );
+}
+
+#[tokio::test]
+async fn concurrent_results_keep_the_sequential_order() {
+    let url = run_api().await;
+    let s = std::include_str!("../src/testdata/multi_endpoint.yml");
+
+    let run = |concurrency| {
+        let openapi_schema = fiuto::parse_openapi(s).unwrap();
+        fiuto::Driller::new(openapi_schema)
+            .base_url(url.clone())
+            .concurrency(concurrency)
+            .run()
+    };
+    let sequential = run(1).await.unwrap().results;
+    let concurrent = run(6).await.unwrap().results;
+
+    let order = |results: &[Vec<fiuto::CallResult>]| -> Vec<(String, String, String)> {
+        results
+            .iter()
+            .flatten()
+            .map(|c| (c.method.clone(), c.path.clone(), c.variant.clone()))
+            .collect()
+    };
+    assert_eq!(order(&sequential), order(&concurrent));
+}
+
+#[tokio::test]
+async fn slow_responses_time_out() {
+    let s = std::include_str!("../src/testdata/get_users_query.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(run_slow_api().await)
+        .client(fiuto::ClientOptions {
+            timeout: Some(std::time::Duration::from_millis(20)),
+            ..fiuto::ClientOptions::default()
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert!(r[0].iter().all(|c| c.outcome == fiuto::Outcome::Timeout));
+}
+
+#[tokio::test]
+async fn requests_go_through_the_proxy() {
+    // The test server routes on the path alone, so it can stand in for a
+    // forward proxy: the spec's host is never resolved.
+    let proxy = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url("http://fiuto.invalid")
+        .client(fiuto::ClientOptions {
+            proxy: Some(proxy),
+            keep_alive: false,
+            ..fiuto::ClientOptions::default()
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r[0][0].outcome, fiuto::Outcome::Response);
+    assert_eq!(r[0][0].status_code, 200);
+}
+
+/// A server rejecting the first `limited` requests with `status` and the given
+/// headers, then answering normally.
+async fn run_rate_limited_api(
+    limited: usize,
+    status: axum::http::StatusCode,
+    headers: &'static [(&'static str, &'static str)],
+) -> String {
+    let seen = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let app = axum::Router::new().route(
+        "/api/v1/org/info",
+        axum::routing::get(move || async move {
+            let n = seen.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if n < limited {
+                let mut resp = axum::response::IntoResponse::into_response(status);
+                for (name, value) in headers {
+                    resp.headers_mut()
+                        .insert(*name, axum::http::HeaderValue::from_static(value));
+                }
+                resp
+            } else {
+                axum::response::IntoResponse::into_response(axum::Json("info"))
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    base_url
+}
+
+#[tokio::test]
+async fn rate_limited_call_is_retried_after_retry_after() {
+    let url = run_rate_limited_api(
+        1,
+        axum::http::StatusCode::TOO_MANY_REQUESTS,
+        &[("retry-after", "1")],
+    )
+    .await;
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let start = std::time::Instant::now();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .throttle(fiuto::Throttle {
+            retries: 2,
+            ..fiuto::Throttle::default()
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+    assert_eq!(r[0].len(), 1, "the retry replaces the rejected call");
+    assert_eq!(r[0][0].status_code, 200);
+    assert_eq!(r[0][0].retries, 1);
+}
+
+#[tokio::test]
+async fn unavailable_without_hints_backs_off_until_retries_run_out() {
+    let url = run_rate_limited_api(3, axum::http::StatusCode::SERVICE_UNAVAILABLE, &[]).await;
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let start = std::time::Instant::now();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .throttle(fiuto::Throttle {
+            retries: 1,
+            ..fiuto::Throttle::default()
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // one 0.5s backoff, then the last answer is recorded as it is
+    assert!(start.elapsed() >= std::time::Duration::from_millis(500));
+    assert_eq!(r[0][0].status_code, 503);
+    assert_eq!(r[0][0].retries, 1);
+}
+
+#[tokio::test]
+async fn rate_limit_without_retries_is_recorded() {
+    let url = run_rate_limited_api(
+        1,
+        axum::http::StatusCode::TOO_MANY_REQUESTS,
+        &[("retry-after", "1")],
+    )
+    .await;
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r[0][0].status_code, 429);
+    assert_eq!(r[0][0].retries, 0);
+}
+
+#[tokio::test]
+async fn rate_spaces_out_requests() {
+    let url = run_api().await;
+
+    // 9 requests at 20 per second: 8 gaps of 50ms
+    let s = std::include_str!("../src/testdata/multi_endpoint.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let start = std::time::Instant::now();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .throttle(fiuto::Throttle {
+            rate: Some(20.0),
+            ..fiuto::Throttle::default()
+        })
+        .concurrency(4)
+        .run()
+        .await
+        .unwrap()
+        .results;
+    let elapsed = start.elapsed();
+
+    let total: usize = r.iter().map(std::vec::Vec::len).sum();
+    assert_eq!(total, 9);
+    assert!(
+        elapsed >= std::time::Duration::from_millis(400),
+        "took {elapsed:?}"
+    );
+}
+
+#[tokio::test]
+async fn api_keys_are_sent_where_their_schemes_say() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/api_key_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .auth("headerKey", "k-header")
+        .auth("queryKey", "k-query")
+        .auth("cookieKey", "k-cookie")
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let calls: Vec<&fiuto::CallResult> = r.iter().flatten().collect();
+    assert_eq!(calls.len(), 4);
+    for c in &calls {
+        assert_eq!(c.status_code, 200, "{} was not authorized", c.path);
+    }
+
+    // the secrets stay out of the results
+    let query = calls
+        .iter()
+        .find(|c| c.path.ends_with("/keys/query"))
+        .unwrap();
+    assert_eq!(query.query, "api_key=<redacted>");
+    assert!(!query.path.contains("k-query"));
+    let both = calls
+        .iter()
+        .find(|c| c.path.ends_with("/keys/both"))
+        .unwrap();
+    assert_eq!(both.request_headers["x-api-key"], "<redacted>");
+    assert_eq!(both.request_headers["cookie"], "<redacted>");
+}
+
+#[tokio::test]
+async fn api_keys_without_secrets_are_not_sent() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/api_key_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .auth("headerKey", "k-header")
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let status = |suffix: &str| {
+        r.iter()
+            .flatten()
+            .find(|c| c.path.ends_with(suffix))
+            .unwrap()
+            .status_code
+    };
+    assert_eq!(status("/keys/header"), 200);
+    assert_eq!(status("/keys/query"), 401);
+    // the cookie key is missing, so the combined requirement fails
+    assert_eq!(status("/keys/both"), 401);
+}
+
+#[tokio::test]
+async fn http_schemes_are_matched_by_name() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/http_auth_schemes.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .basic("user", "pass")
+        .auth("digestAuth", "user:pass")
+        .auth("tokenAuth", "t0k3n")
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let calls: Vec<&fiuto::CallResult> = r.iter().flatten().collect();
+    assert_eq!(calls.len(), 3);
+    for c in calls {
+        assert_eq!(c.status_code, 200, "{} was not authorized", c.path);
+        // the Digest answer sent after the challenge is recorded too
+        assert_eq!(c.request_headers["authorization"], "<redacted>");
+    }
+}
+
+#[tokio::test]
+async fn basic_login_is_not_sent_to_bearer_schemes() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/http_auth_schemes.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .jwt("t0k3n")
+        .basic("user", "wrong")
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // no bearer scheme here, and Token is not bearer
+    assert!(r.iter().flatten().all(|c| c.status_code == 401));
+}
+
+/// An authorization server stand-in issuing `tok-N` tokens that expire after
+/// `expires_in` seconds, next to endpoints accepting them. With
+/// `reject_first` the first token is refused as if revoked. Returns the base
+/// URL and the grants asked for, as `grant_type scope`.
+async fn run_oauth2_api(
+    expires_in: u64,
+    reject_first: bool,
+) -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    let grants = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let issued = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+    let token = {
+        let grants = grants.clone();
+        move |headers: axum::http::HeaderMap,
+              axum::Form(form): axum::Form<std::collections::HashMap<String, String>>| async move {
+            // fiuto:s3cret
+            let client_ok = authorization(&headers) == "Basic Zml1dG86czNjcmV0";
+            let login_ok = form.get("grant_type").map(String::as_str) != Some("password")
+                || (form.get("username").map(String::as_str) == Some("alice")
+                    && form.get("password").map(String::as_str) == Some("wonderland"));
+            if !client_ok || !login_ok {
+                return axum::response::IntoResponse::into_response(
+                    axum::http::StatusCode::UNAUTHORIZED,
+                );
+            }
+
+            let field = |name: &str| form.get(name).cloned().unwrap_or_default();
+            grants
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", field("grant_type"), field("scope")));
+            let n = issued.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            axum::response::IntoResponse::into_response(axum::Json(serde_json::json!({
+                "access_token": format!("tok-{n}"),
+                "token_type": "Bearer",
+                "expires_in": expires_in,
+                "refresh_token": format!("refresh-{n}"),
+            })))
+        }
+    };
+    let protected = move |headers: axum::http::HeaderMap| async move {
+        let token = authorization(&headers).strip_prefix("Bearer tok-");
+        status_if(token.is_some() && !(reject_first && token == Some("0")))
+    };
+
+    let app = axum::Router::new()
+        .route("/oauth/token", axum::routing::post(token))
+        .route("/api/v1/oauth/client", axum::routing::get(protected))
+        .route("/api/v1/oauth/reports", axum::routing::get(protected))
+        .route("/api/v1/oauth/user", axum::routing::get(protected));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (base_url, grants)
+}
+
+fn oauth2_client(url: &str) -> fiuto::OAuth2 {
+    fiuto::OAuth2 {
+        client_id: "fiuto".to_owned(),
+        client_secret: Some("s3cret".to_owned()),
+        token_url: Some(format!("{url}/oauth/token")),
+        ..fiuto::OAuth2::default()
+    }
+}
+
+fn status_of(results: &[Vec<fiuto::CallResult>], path: &str) -> u16 {
+    results
+        .iter()
+        .flatten()
+        .find(|c| c.path.ends_with(path))
+        .unwrap()
+        .status_code
+}
+
+#[tokio::test]
+async fn client_credentials_token_is_fetched_once_per_scope_set() {
+    let (url, grants) = run_oauth2_api(3600, false).await;
+
+    let s = std::include_str!("../src/testdata/oauth2_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .oauth2(oauth2_client(&url))
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(status_of(&r, "/oauth/client"), 200);
+    assert_eq!(status_of(&r, "/oauth/reports"), 200);
+    // the token is recorded as sent, redacted
+    let client = r
+        .iter()
+        .flatten()
+        .find(|c| c.path.ends_with("/oauth/client"));
+    assert_eq!(
+        client.unwrap().request_headers["authorization"],
+        "<redacted>"
+    );
+    // no login, so the password flow gets no token
+    assert_eq!(status_of(&r, "/oauth/user"), 401);
+    assert_eq!(
+        *grants.lock().unwrap(),
+        ["client_credentials read", "client_credentials read reports"]
+    );
+
+    // the same scopes for every operation share one token
+    let (url, grants) = run_oauth2_api(3600, false).await;
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .oauth2(fiuto::OAuth2 {
+            scopes: vec!["read".to_owned()],
+            ..oauth2_client(&url)
+        })
+        .concurrency(3)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(status_of(&r, "/oauth/reports"), 200);
+    assert_eq!(*grants.lock().unwrap(), ["client_credentials read"]);
+}
+
+#[tokio::test]
+async fn password_flow_sends_the_resource_owner_login() {
+    let (url, grants) = run_oauth2_api(3600, false).await;
+
+    let s = std::include_str!("../src/testdata/oauth2_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .oauth2(fiuto::OAuth2 {
+            login: Some(("alice".to_owned(), "wonderland".to_owned())),
+            ..oauth2_client(&url)
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(status_of(&r, "/oauth/user"), 200);
+    assert!(grants.lock().unwrap().contains(&"password ".to_owned()));
+}
+
+#[tokio::test]
+async fn expired_token_is_refreshed() {
+    // expiring within the renewal margin, so each call needs a new one
+    let (url, grants) = run_oauth2_api(1, false).await;
+
+    let s = std::include_str!("../src/testdata/oauth2_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .oauth2(fiuto::OAuth2 {
+            scopes: vec!["read".to_owned()],
+            ..oauth2_client(&url)
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(status_of(&r, "/oauth/client"), 200);
+    assert_eq!(status_of(&r, "/oauth/reports"), 200);
+    assert_eq!(
+        *grants.lock().unwrap(),
+        ["client_credentials read", "refresh_token read"]
+    );
+}
+
+#[tokio::test]
+async fn rejected_token_is_renewed_and_the_call_sent_again() {
+    let (url, grants) = run_oauth2_api(3600, true).await;
+
+    let s = std::include_str!("../src/testdata/oauth2_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url.clone())
+        .oauth2(fiuto::OAuth2 {
+            scopes: vec!["read".to_owned()],
+            ..oauth2_client(&url)
+        })
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(status_of(&r, "/oauth/client"), 200);
+    assert_eq!(status_of(&r, "/oauth/reports"), 200);
+    assert_eq!(
+        *grants.lock().unwrap(),
+        ["client_credentials read", "refresh_token read"]
+    );
+}
+
+#[tokio::test]
+async fn auth_check_flags_the_probes_let_through() {
+    let url = run_api().await;
+
+    // the endpoint takes any bearer token without validating it
+    let s = std::include_str!("../src/testdata/get_more_info_with_jwt.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .jwt("t0k3n")
+        .auth_check(true)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let calls = r.first().unwrap();
+    assert_eq!(calls.len(), 4);
+    assert_eq!(calls[0].auth_probe, None);
+    assert_eq!(calls[0].status_code, 200);
+
+    let probes: Vec<_> = calls[1..]
+        .iter()
+        .map(|c| (c.auth_probe.unwrap(), c.status_code, c.auth_bypass))
+        .collect();
+    assert_eq!(
+        probes,
+        [
+            (fiuto::AuthProbe::Anonymous, 401, false),
+            // an expired JWT is accepted
+            (fiuto::AuthProbe::InvalidCredentials, 200, true),
+            // the token sent as Basic is not a bearer token
+            (fiuto::AuthProbe::WrongScheme, 401, false),
+        ]
+    );
+    assert!(
+        calls[1..]
+            .iter()
+            .all(|c| c.expected == fiuto::Expectation::Invalid)
+    );
+}
+
+#[tokio::test]
+async fn auth_probes_count_against_max_requests() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_more_info_with_jwt.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .jwt("t0k3n")
+        .auth_check(true)
+        .max_requests_per_operation(2)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    // the operation itself still goes first, then as many probes as fit
+    let probes: Vec<_> = r[0].iter().map(|c| c.auth_probe).collect();
+    assert_eq!(probes, [None, Some(fiuto::AuthProbe::Anonymous)]);
+}
+
+#[tokio::test]
+async fn auth_check_passes_when_every_probe_is_refused() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/api_key_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .auth("headerKey", "k-header")
+        .auth("queryKey", "k-query")
+        .auth("cookieKey", "k-cookie")
+        .auth_check(true)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    for calls in &r {
+        let probes: Vec<_> = calls.iter().filter(|c| c.auth_probe.is_some()).collect();
+        assert_eq!(probes.len(), 3, "{} has no probes", calls[0].path);
+        assert!(
+            probes
+                .iter()
+                .all(|c| c.status_code == 401 && !c.auth_bypass)
+        );
+        assert!(
+            calls
+                .iter()
+                .filter(|c| c.auth_probe.is_none())
+                .all(|c| c.status_code == 200)
+        );
+    }
+}
+
+#[tokio::test]
+async fn auth_check_skips_public_endpoints() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .auth_check(true)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    assert_eq!(r.iter().flatten().count(), 1);
+    assert!(r.iter().flatten().all(|c| c.auth_probe.is_none()));
+}
+
+#[tokio::test]
+async fn public_optional_and_inherited_security_are_told_apart() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/optional_security.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .jwt("t0k3n")
+        .auth_check(true)
+        .run()
+        .await
+        .unwrap()
+        .results;
+
+    let calls = |path: &str| -> Vec<(&str, u16, bool)> {
+        r.iter()
+            .flatten()
+            .filter(|c| c.path.ends_with(path))
+            .map(|c| {
+                let sent = c.request_headers.contains_key("authorization");
+                (c.variant.as_str(), c.status_code, sent)
+            })
+            .collect()
+    };
+
+    // `security: []` makes it public: no token, no probes
+    assert_eq!(calls("/org/info"), [("single", 200, false)]);
+    // `{}` makes the token optional, so the call goes both ways
+    assert_eq!(
+        calls("/more/info"),
+        [("single", 200, true), ("without credentials", 401, false)]
+    );
+    // no `security` of its own inherits the spec-level bearer, probes included
+    let profile = calls("/profile");
+    assert_eq!(profile.len(), 4);
+    assert_eq!(profile[0], ("single", 200, true));
+}
+
+#[tokio::test]
+async fn servers_are_picked_per_operation_and_expanded() {
+    let url = run_api().await;
+    let port = url.rsplit(':').next().unwrap().to_owned();
+    let local = format!("http://127.0.0.1:{port}");
+
+    let s = std::include_str!("../src/testdata/servers_per_operation.yml");
+    for selector in ["1", "local"] {
+        let openapi_schema = fiuto::parse_openapi(s).unwrap();
+        let r = fiuto::Driller::new(openapi_schema)
+            .server(selector)
+            .server_var("port", port.clone())
+            .run()
+            .await
+            .unwrap()
+            .results;
+
+        let paths: Vec<(&str, u16)> = r
+            .iter()
+            .flatten()
+            .map(|c| (c.path.as_str(), c.status_code))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (format!("{local}/api/v1/profile/avatar").as_str(), 200),
+                (format!("{local}/api/v1/profile").as_str(), 200),
+                (format!("{local}/api/v1/org/info").as_str(), 200),
+            ]
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_spec_without_servers_is_an_error() {
+    let s = std::include_str!("../src/testdata/get_info.yml");
+    let mut openapi_schema = fiuto::parse_openapi(s).unwrap();
+    openapi_schema.servers.clear();
+
+    let r = fiuto::Driller::new(openapi_schema).run().await;
+
+    assert!(matches!(r, Err(fiuto::Error::NoServers)));
+}
+
+#[tokio::test]
+async fn a_server_selector_matching_nothing_is_an_error() {
+    let s = std::include_str!("../src/testdata/multiple_servers.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+
+    let r = fiuto::Driller::new(openapi_schema)
+        .server("nowhere")
+        .run()
+        .await;
+
+    assert!(matches!(r, Err(fiuto::Error::Spec(_))));
+}
+
+#[tokio::test]
+async fn an_unresolved_payload_reference_skips_only_its_operation() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login_unresolved_ref.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .run()
+        .await
+        .unwrap();
+
+    // GETs come first: the sound one is drilled, the broken POST left empty
+    assert_eq!(r.results.len(), 2);
+    assert_eq!(r.results[0].len(), 1);
+    assert_eq!(r.results[0][0].status_code, 200);
+    assert!(r.results[1].is_empty());
+
+    // and listed with why, to tell it apart from an operation without calls
+    let [(skipped, fiuto::Error::Payload { operation, message })] = r.skipped.as_slice() else {
+        panic!("expected a payload error, got {:?}", r.skipped);
+    };
+    assert_eq!(skipped, "POST /api/v1/login");
+    assert_eq!(operation, "POST /api/v1/login");
+    assert!(message.contains("Credentials"), "{message}");
+}
+
+#[tokio::test]
+async fn an_unresolved_payload_reference_is_skipped_with_fail_fast_too() {
+    let url = run_api().await;
+
+    let s = std::include_str!("../src/testdata/post_login_unresolved_ref.yml");
+    let openapi_schema = fiuto::parse_openapi(s).unwrap();
+    let r = fiuto::Driller::new(openapi_schema)
+        .base_url(url)
+        .fail_fast(true)
+        .run()
+        .await
+        .unwrap();
+
+    // fail fast is about transport failures only
+    assert_eq!(r.results[0][0].status_code, 200);
+    assert_eq!(r.skipped.len(), 1);
+}